@@ -0,0 +1,28 @@
+//! Compares the bucket queue search for day 17 against the original binary heap search.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use aoc2023::day17::{CrucibleRules, Day, HeatLossMap};
+use aoc2023::SolutionSilver;
+
+fn day_17_queue_benches(c: &mut Criterion) {
+    let map = HeatLossMap::parse(Day::INPUT_REAL);
+    let end = (map.width - 1, map.height - 1);
+
+    for (name, rules) in [
+        ("crucible", CrucibleRules::CRUCIBLE),
+        ("ultra crucible", CrucibleRules::ULTRA_CRUCIBLE),
+    ] {
+        let mut group = c.benchmark_group(format!("Day 17 {name} (real)"));
+        group.bench_function("bucket queue", |b| {
+            b.iter(|| map.find_path((0, 0), black_box(end), &rules))
+        });
+        group.bench_function("binary heap", |b| {
+            b.iter(|| map.find_path_binary_heap((0, 0), black_box(end), &rules))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(day_17_queue, day_17_queue_benches);
+criterion_main!(day_17_queue);
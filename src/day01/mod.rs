@@ -14,7 +14,7 @@ impl SolutionSilver<usize> for Day {
             .map(|l| l.iter().filter(|c| *c & 0xF0 == 0x30))
             .map(|mut v| {
                 let first = v.next().unwrap() & 0x0F;
-                let last = v.last().unwrap_or(&first) & 0x0F;
+                let last = v.next_back().unwrap_or(&first) & 0x0F;
                 (first * 10 + last) as usize
             })
             .sum();
@@ -94,6 +94,8 @@ impl SolutionGold<usize, usize> for Day {
             })
             .map(|mut v| {
                 let first = v.next().unwrap() & 0xF;
+                // NOTE: `next_back` would break the stateful `skip_ahead` logic above
+                #[allow(clippy::double_ended_iterator_last)]
                 let last = v.last().unwrap_or(first) & 0xF;
                 first * 10 + last
             })
@@ -72,7 +72,7 @@ impl SolutionGold<usize, usize> for Day {
             .collect::<Vec<usize>>();
         let mut nums = nums
             .chunks(2)
-            .map(|a| (a[0]..(a[0] + a[1])))
+            .map(|a| a[0]..(a[0] + a[1]))
             .collect::<Vec<Range<usize>>>();
         _ = lines.next().unwrap();
 
@@ -1,4 +1,8 @@
+use std::ops::RangeInclusive;
+
 use crate::utils::fast_parse_int_from_bytes;
+#[cfg(test)]
+use crate::utils::xorshift64;
 
 use super::*;
 
@@ -23,28 +27,7 @@ impl SolutionSilver<usize> for Day {
         times
             .into_iter()
             .zip(distances)
-            .map(|(t, d)| {
-                // find push_time so that push_time * (t-push_time) > d
-                // push_time*t - push_time^2 > d
-                // push_time^2 - push_time*t - d == 0 (rounded somehow)
-
-                // formula for second-degree polynomial is (-b +- sqrt(b^2 - 4ac)) / 2a
-
-                let a = 1.;
-                let b = t as f32;
-                let c = d as f32;
-                let x1 = (b + f32::sqrt(b * b - 4. * a * c)) / (2. * a);
-                let x2 = (b - f32::sqrt(b * b - 4. * a * c)) / (2. * a);
-
-                debug_assert!((x1 * b - x1 * x1 - c).abs() < 0.001);
-                debug_assert!((x2 * b - x2 * x2 - c).abs() < 0.001);
-
-                let (x_min, x_max) = (x1.min(x2), x1.max(x2));
-                let x_min = (x_min + 1.).floor();
-                let x_max = (x_max - 1.).ceil();
-
-                (x_max - x_min + 1.) as usize
-            })
+            .map(|(t, d)| count_winning_hold_times(t as u64, d as u64) as usize)
             .product()
     }
 }
@@ -52,44 +35,56 @@ impl SolutionSilver<usize> for Day {
 impl SolutionGold<usize, usize> for Day {
     fn calculate_gold(input: &str) -> usize {
         let (time, distance) = input.split_once('\n').unwrap();
-        let times = time.as_bytes()[11..]
-            .split(|b| *b == b' ')
-            .filter(|s| !s.is_empty())
-            .map(fast_parse_int_from_bytes);
-        let distances = distance.as_bytes()[11..]
-            .split(|b| *b == b' ')
-            .filter(|s| !s.is_empty())
-            .map(fast_parse_int_from_bytes);
 
-        let (t, d) = times.into_iter().zip(distances).fold((0, 0), |acc, val| {
-            (
-                acc.0 * 10f32.powi((val.0 as f32).log10() as i32 + 1) as usize + val.0,
-                acc.1 * 10f32.powi((val.1 as f32).log10() as i32 + 1) as usize + val.1,
-            )
-        });
+        // the kerning is bad, so all digits on a line form a single number
+        let concat_digits = |line: &str| {
+            line.bytes()
+                .filter(u8::is_ascii_digit)
+                .fold(0u64, |acc, b| acc * 10 + (b & 0x0f) as u64)
+        };
+        let t = concat_digits(&time[11..]);
+        let d = concat_digits(distance[11..].trim_end());
 
-        // find push_time so that push_time * (t-push_time) > d
-        // push_time*t - push_time^2 > d
-        // push_time^2 - push_time*t - d == 0 (rounded somehow)
-
-        // formula for second-degree polynomial is (-b +- sqrt(b^2 - 4ac)) / 2a
+        count_winning_hold_times(t, d) as usize
+    }
+}
 
-        // NOTE: the numbers in part 2 are a lot larger so 64-bit floats are required
-        let a = 1.;
-        let b = t as f64;
-        let c = d as f64;
-        let x1 = (b + f64::sqrt(b * b - 4. * a * c)) / (2. * a);
-        let x2 = (b - f64::sqrt(b * b - 4. * a * c)) / (2. * a);
+/// Returns the amount of button hold times that beat the record `distance` in a race that lasts
+/// `time` milliseconds.
+pub fn count_winning_hold_times(time: u64, distance: u64) -> u64 {
+    winning_hold_times(time, distance).map_or(0, |r| r.end() - r.start() + 1)
+}
 
-        debug_assert!((x1 * b - x1 * x1 - c).abs() < 0.001);
-        debug_assert!((x2 * b - x2 * x2 - c).abs() < 0.001);
+/// Returns the range of button hold times that beat the record `distance` in a race that lasts
+/// `time` milliseconds, or `None` if the record can't be beaten.
+///
+/// Holding the button for `h` ms travels `h * (time - h)` mm, so this solves
+/// `h^2 - time*h + distance < 0` using only integer math. The range is symmetric around
+/// `time / 2`.
+pub fn winning_hold_times(time: u64, distance: u64) -> Option<RangeInclusive<u64>> {
+    let travelled = |hold: u64| u128::from(hold) * u128::from(time - hold);
+    let beats_record = |hold: u64| travelled(hold) > u128::from(distance);
+
+    // the best hold time is right in the middle, if that doesn't win nothing will
+    if !beats_record(time / 2) {
+        return None;
+    }
 
-        let (x_min, x_max) = (x1.min(x2), x1.max(x2));
-        let x_min = (x_min + 1.).floor();
-        let x_max = (x_max - 1.).ceil();
+    // lower root is (time - sqrt(time^2 - 4*distance)) / 2. the discriminant is positive because
+    // the record can be beaten.
+    let discriminant = u128::from(time) * u128::from(time) - 4 * u128::from(distance);
+    let sqrt = discriminant.isqrt() as u64;
+    let mut lower = (time - sqrt) / 2;
 
-        (x_max - x_min + 1.) as usize
+    // the truncated square root and division can put us off by one on either side of the root
+    while !beats_record(lower) {
+        lower += 1;
+    }
+    while lower > 0 && beats_record(lower - 1) {
+        lower -= 1;
     }
+
+    Some(lower..=(time - lower))
 }
 
 #[test]
@@ -115,3 +110,67 @@ fn test_gold_real() {
     let output = Day::calculate_gold(Day::INPUT_REAL);
     assert_eq!(34454850, output);
 }
+
+#[test]
+fn test_winning_hold_times_matches_brute_force() {
+    let brute_force = |t: u64, d: u64| (0..=t).filter(|h| h * (t - h) > d).count() as u64;
+
+    let mut rng = 0x2023_0006;
+    for _ in 0..10_000 {
+        let t = xorshift64(&mut rng) % 2_000;
+        let max_distance = t * t / 4;
+        let d = xorshift64(&mut rng) % (max_distance + 2);
+        assert_eq!(
+            brute_force(t, d),
+            count_winning_hold_times(t, d),
+            "t={t} d={d}"
+        );
+
+        // records that are exactly reachable must not count as a win
+        if t > 0 {
+            let hold = xorshift64(&mut rng) % t;
+            let d = hold * (t - hold);
+            assert_eq!(
+                brute_force(t, d),
+                count_winning_hold_times(t, d),
+                "t={t} d={d}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_winning_hold_times_large() {
+    let mut rng = 0x2023_0606;
+    for _ in 0..10_000 {
+        let t = xorshift64(&mut rng) >> (xorshift64(&mut rng) % 64);
+        let hold = xorshift64(&mut rng) % (t / 2 + 1);
+        let tie = u128::from(hold) * u128::from(t - hold);
+        let Ok(d) = u64::try_from(tie) else {
+            continue;
+        };
+
+        let range = winning_hold_times(t, d);
+        if hold == t / 2 {
+            assert_eq!(None, range, "t={t} d={d}");
+            continue;
+        }
+        let range = range.unwrap();
+        assert_eq!(hold + 1, *range.start(), "t={t} d={d}");
+        assert_eq!(t - hold - 1, *range.end(), "t={t} d={d}");
+    }
+}
+
+#[test]
+fn test_winning_hold_times_edge_cases() {
+    assert_eq!(None, winning_hold_times(0, 0));
+    assert_eq!(None, winning_hold_times(1, 0));
+    assert_eq!(Some(1..=1), winning_hold_times(2, 0));
+    assert_eq!(None, winning_hold_times(4, 4));
+    assert_eq!(Some(2..=2), winning_hold_times(4, 3));
+    assert_eq!(Some(11..=19), winning_hold_times(30, 200));
+    assert_eq!(
+        Some(1..=(u64::MAX - 1)),
+        winning_hold_times(u64::MAX, u64::MAX - 2)
+    );
+}
@@ -1,7 +1,5 @@
 #![allow(clippy::naive_bytecount)]
 
-use std::collections::BTreeMap;
-
 use crate::utils::fast_parse_int_from_bytes;
 
 use super::*;
@@ -14,178 +12,403 @@ impl SolutionSilver<usize> for Day {
     const INPUT_REAL: &'static str = include_str!("input_real.txt");
 
     fn calculate_silver(input: &str) -> usize {
-        let plays = input
-            .lines()
-            .map(|l| {
-                let (hand, score) = l.split_once(' ').unwrap();
-                let score = fast_parse_int_from_bytes(score.as_bytes());
-
-                debug_assert_eq!(5, hand.len());
-                let hand: [u8; 5] = hand.as_bytes().try_into().unwrap();
-                let hand = hand.map(|b| match b {
-                    b'A' => 14,
-                    b'K' => 13,
-                    b'Q' => 12,
-                    b'J' => 11,
-                    b'T' => 10,
-                    b => b & 0x0F,
-                });
-
-                (hand, score)
-            })
-            .map(|(hand, score)| {
-                let hand_type = get_hand_rank_1(hand);
-                ((hand_type, hand), score)
-            })
-            .collect::<BTreeMap<(_, _), _>>();
-
-        plays
-            .into_iter()
-            .enumerate()
-            .map(|(i, (_, val))| (i + 1) * val)
-            .sum()
+        total_winnings(input, &Ruleset::standard())
     }
 }
 
-fn get_hand_rank_1(hand: [u8; 5]) -> usize {
-    let mut char_count_lut = [0u8; 15];
-    for char in &hand {
-        char_count_lut[*char as usize] += 1;
+impl SolutionGold<usize, usize> for Day {
+    fn calculate_gold(input: &str) -> usize {
+        total_winnings(input, &Ruleset::jokers())
     }
+}
 
-    if char_count_lut.iter().any(|v| *v == 5) {
-        return 6; // 5 of a kind
-    }
-    if char_count_lut.iter().any(|v| *v == 4) {
-        return 5; // 4 of a kind
-    }
-    if char_count_lut.iter().any(|v| *v == 3) && char_count_lut.iter().any(|v| *v == 2) {
-        return 4; // full house
+/// Describes a variant of Camel Cards.
+#[derive(Debug, Clone)]
+pub struct Ruleset {
+    /// All valid cards, from weakest to strongest. This is also the tie-break order.
+    pub card_order: Vec<u8>,
+    /// Cards that act like whatever card makes the hand strongest.
+    pub wildcards: Vec<u8>,
+    pub hand_size: usize,
+    /// Hand categories, from weakest to strongest.
+    pub categories: Vec<HandCategory>,
+}
+
+/// A type of hand, such as "full house".
+#[derive(Debug, Clone)]
+pub struct HandCategory {
+    pub name: &'static str,
+    /// The minimum size of the largest groups of equal cards, in descending order. A full house
+    /// is `[3, 2]`, high card is `[]`.
+    pub groups: Vec<usize>,
+}
+
+/// A hand that has been scored by [`Ruleset::evaluate`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EvaluatedHand {
+    /// Index into [`Ruleset::categories`].
+    pub category: usize,
+    /// The index of each card in [`Ruleset::card_order`], used for tie-breaks.
+    pub strengths: Vec<usize>,
+    /// The hand after wildcards have been substituted.
+    pub effective: Vec<u8>,
+}
+
+impl HandCategory {
+    pub fn new(name: &'static str, groups: &[usize]) -> Self {
+        Self {
+            name,
+            groups: groups.to_vec(),
+        }
     }
-    if char_count_lut.iter().any(|v| *v == 3) {
-        return 3; // 3 of a kind
+}
+
+impl Ruleset {
+    /// The rules used in part 1.
+    pub fn standard() -> Self {
+        Self {
+            card_order: b"23456789TJQKA".to_vec(),
+            wildcards: vec![],
+            hand_size: 5,
+            categories: Self::poker_categories(),
+        }
     }
-    if char_count_lut.iter().filter(|v| **v == 2).count() == 2 {
-        return 2; // 2 pair
+
+    /// The rules used in part 2, where `J` is a joker.
+    pub fn jokers() -> Self {
+        Self {
+            card_order: b"J23456789TQKA".to_vec(),
+            wildcards: b"J".to_vec(),
+            hand_size: 5,
+            categories: Self::poker_categories(),
+        }
     }
-    if char_count_lut.iter().any(|v| *v == 2) {
-        return 1; // pair
+
+    /// The hand categories for 5-card hands, from high card to five of a kind.
+    pub fn poker_categories() -> Vec<HandCategory> {
+        vec![
+            HandCategory::new("high card", &[]),
+            HandCategory::new("one pair", &[2]),
+            HandCategory::new("two pair", &[2, 2]),
+            HandCategory::new("three of a kind", &[3]),
+            HandCategory::new("full house", &[3, 2]),
+            HandCategory::new("four of a kind", &[4]),
+            HandCategory::new("five of a kind", &[5]),
+        ]
     }
 
-    0
-}
+    /// Scores a hand. Comparing the results orders hands from weakest to strongest.
+    ///
+    /// Panics if the hand has the wrong size or contains an unknown card.
+    pub fn evaluate(&self, hand: &[u8]) -> EvaluatedHand {
+        assert_eq!(self.hand_size, hand.len(), "wrong hand size");
 
-impl SolutionGold<usize, usize> for Day {
-    fn calculate_gold(input: &str) -> usize {
-        let plays = input
-            .lines()
-            .map(|l| {
-                let (hand, score) = l.split_once(' ').unwrap();
-                let score = fast_parse_int_from_bytes(score.as_bytes());
-
-                debug_assert_eq!(5, hand.len());
-                let hand: [u8; 5] = hand.as_bytes().try_into().unwrap();
-                let hand = hand.map(|b| match b {
-                    b'A' => 13,
-                    b'K' => 12,
-                    b'Q' => 11,
-                    b'T' => 10,
-                    b'J' => 1,
-                    b => b & 0x0F,
-                });
-
-                (hand, score)
+        let strengths = hand
+            .iter()
+            .map(|card| {
+                self.card_order
+                    .iter()
+                    .position(|c| c == card)
+                    .unwrap_or_else(|| panic!("unknown card {:?}", *card as char))
             })
-            .map(|(hand, score)| {
-                let hand_type = get_hand_rank_2(hand);
-                ((hand_type, hand), score)
-            })
-            .collect::<BTreeMap<(_, _), _>>();
+            .collect::<Vec<_>>();
 
-        plays
-            .into_iter()
-            .enumerate()
-            .map(|(i, (_, val))| (i + 1) * val)
-            .sum()
-    }
-}
+        let mut effective = hand.to_vec();
+        let wild_positions = (0..hand.len())
+            .filter(|&i| self.wildcards.contains(&hand[i]))
+            .collect::<Vec<_>>();
+        let substitutes = self
+            .card_order
+            .iter()
+            .copied()
+            .filter(|c| !self.wildcards.contains(c))
+            .collect::<Vec<_>>();
 
-fn get_hand_rank_2(hand: [u8; 5]) -> usize {
-    let mut card_lut = [0u8; 12];
-    for char in &hand {
-        if *char == 1 {
-            // joker
-            card_lut[0] += 1;
-            card_lut[1] += 1;
-            card_lut[2] += 1;
-            card_lut[3] += 1;
-            card_lut[4] += 1;
-            card_lut[5] += 1;
-            card_lut[6] += 1;
-            card_lut[7] += 1;
-            card_lut[8] += 1;
-            card_lut[9] += 1;
-            card_lut[10] += 1;
-            card_lut[11] += 1;
+        let category = if wild_positions.is_empty() || substitutes.is_empty() {
+            self.classify(hand)
         } else {
-            card_lut[*char as usize - 2] += 1;
+            let mut best = None;
+            self.find_best_substitution(
+                &mut effective,
+                &wild_positions,
+                &substitutes,
+                0,
+                &mut best,
+            );
+            let (category, best_hand) = best.unwrap();
+            effective = best_hand;
+            category
+        };
+
+        EvaluatedHand {
+            category,
+            strengths,
+            effective,
         }
     }
 
-    let card_bitmap = hand.iter().fold(0, |acc, b| acc | 1usize << b);
-    let card_types_no_joker = (card_bitmap & !0b10).count_ones() as usize;
-    let joker_count = hand.iter().filter(|c| **c == 1).count();
+    /// Returns the index of the strongest category that `hand` satisfies, ignoring wildcards.
+    pub fn classify(&self, hand: &[u8]) -> usize {
+        let mut groups = vec![];
+        let mut seen = vec![];
+        for card in hand {
+            if !seen.contains(card) {
+                seen.push(*card);
+                groups.push(hand.iter().filter(|c| *c == card).count());
+            }
+        }
+        groups.sort_unstable_by(|a, b| b.cmp(a));
 
-    if card_lut.iter().any(|v| *v >= 5) {
-        return 6; // 5 of a kind
-    }
-    if card_lut.iter().any(|v| *v >= 4) {
-        return 5; // 4 of a kind
-    }
-    if card_lut.iter().filter(|v| **v >= 2).count() >= 2 && card_types_no_joker == 2 {
-        return 4; // full house
-    }
-    if card_lut.iter().any(|v| *v >= 3) {
-        return 3; // 3 of a kind
+        self.categories
+            .iter()
+            .rposition(|category| {
+                category.groups.len() <= groups.len()
+                    && category.groups.iter().zip(&groups).all(|(req, g)| g >= req)
+            })
+            .unwrap_or(0)
     }
-    if joker_count >= 2
-        || (joker_count == 1 && card_types_no_joker < 4)
-        || (joker_count == 0 && card_lut.iter().filter(|v| **v >= 2).count() >= 2)
-    {
-        return 2; // 2 pair
+
+    /// Tries every multiset of substitutes for the wildcards. Substitutes are assigned in
+    /// non-decreasing order so each combination is only checked once.
+    fn find_best_substitution(
+        &self,
+        hand: &mut [u8],
+        wild_positions: &[usize],
+        substitutes: &[u8],
+        min_substitute: usize,
+        best: &mut Option<(usize, Vec<u8>)>,
+    ) {
+        let Some((&position, rest)) = wild_positions.split_first() else {
+            let category = self.classify(hand);
+            if best.as_ref().is_none_or(|(best, _)| category > *best) {
+                *best = Some((category, hand.to_vec()));
+            }
+            return;
+        };
+
+        for i in min_substitute..substitutes.len() {
+            hand[position] = substitutes[i];
+            self.find_best_substitution(hand, rest, substitutes, i, best);
+        }
     }
-    if card_lut.iter().any(|v| *v >= 2) {
-        return 1; // pair
+}
+
+/// Parses the list of hands and bids, and returns the sum of each bid multiplied by the rank of
+/// its hand.
+pub fn total_winnings(input: &str, rules: &Ruleset) -> usize {
+    ranked_plays(input, rules)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, _, bid))| (i + 1) * bid)
+        .sum()
+}
+
+/// Returns the plays from weakest to strongest. Identical hands keep their order from the input.
+fn ranked_plays<'a>(input: &'a str, rules: &Ruleset) -> Vec<(&'a [u8], EvaluatedHand, usize)> {
+    let mut plays = parse_plays(input, rules).collect::<Vec<_>>();
+    plays.sort_by(|a, b| a.1.cmp(&b.1));
+    plays
+}
+
+fn parse_plays<'a: 'r, 'r>(
+    input: &'a str,
+    rules: &'r Ruleset,
+) -> impl Iterator<Item = (&'a [u8], EvaluatedHand, usize)> + 'r {
+    input.lines().map(|l| {
+        let (hand, bid) = l.split_once(' ').unwrap();
+        let bid = fast_parse_int_from_bytes(bid.as_bytes());
+
+        (hand.as_bytes(), rules.evaluate(hand.as_bytes()), bid)
+    })
+}
+
+/// Describes why a hand ended up at its rank. Created by [`explain_winnings`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandExplanation<'a> {
+    pub hand: &'a [u8],
+    pub bid: usize,
+    /// 1-based rank, the weakest hand has rank 1.
+    pub rank: usize,
+    pub category: &'static str,
+    /// The hand after wildcards have been substituted.
+    pub effective: Vec<u8>,
+    /// How this hand was separated from the next weaker hand, if any.
+    pub vs_lower: Option<TieBreak>,
+    /// How this hand was separated from the next stronger hand, if any.
+    pub vs_higher: Option<TieBreak>,
+}
+
+/// What decided the order between two neighbouring hands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    /// The hands have different categories.
+    Category,
+    /// The hands have the same category and differ first at the card with this index.
+    Card(usize),
+    /// The hands are identical.
+    Equal,
+}
+
+impl TieBreak {
+    fn between(a: &EvaluatedHand, b: &EvaluatedHand) -> Self {
+        if a.category != b.category {
+            return Self::Category;
+        }
+        a.strengths
+            .iter()
+            .zip(&b.strengths)
+            .position(|(a, b)| a != b)
+            .map_or(Self::Equal, Self::Card)
     }
+}
+
+/// Ranks all hands like [`total_winnings`], but returns the details for each hand ordered from
+/// weakest to strongest.
+pub fn explain_winnings<'a>(input: &'a str, rules: &Ruleset) -> Vec<HandExplanation<'a>> {
+    let plays = ranked_plays(input, rules);
+
+    (0..plays.len())
+        .map(|i| {
+            let (hand, evaluated, bid) = &plays[i];
+            let tie_break = |other: usize| TieBreak::between(evaluated, &plays[other].1);
 
-    0
+            HandExplanation {
+                hand,
+                bid: *bid,
+                rank: i + 1,
+                category: rules.categories[evaluated.category].name,
+                effective: evaluated.effective.clone(),
+                vs_lower: i.checked_sub(1).map(tie_break),
+                vs_higher: (i + 1 < plays.len()).then(|| tie_break(i + 1)),
+            }
+        })
+        .collect()
+}
+
+impl Display for HandExplanation<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hand = String::from_utf8_lossy(self.hand);
+        let effective = String::from_utf8_lossy(&self.effective);
+        write!(
+            f,
+            "#{:<4} {hand} -> {effective} {:<15} bid {:>4} = {:>7}",
+            self.rank,
+            self.category,
+            self.bid,
+            self.rank * self.bid,
+        )?;
+
+        let mut write_tie_break = |label: &str, tie_break: Option<TieBreak>| match tie_break {
+            None => Ok(()),
+            Some(TieBreak::Category) => write!(f, " | {label}: category"),
+            Some(TieBreak::Card(i)) => {
+                write!(f, " | {label}: card {} ({})", i + 1, self.hand[i] as char)
+            }
+            Some(TieBreak::Equal) => write!(f, " | {label}: equal"),
+        };
+        write_tie_break("vs lower", self.vs_lower)?;
+        write_tie_break("vs higher", self.vs_higher)
+    }
 }
 
 #[test]
 fn test_get_rank_hand_2() {
-    let map = |hand: [u8; 5]| {
-        hand.map(|b| match b {
-            b'A' => 13,
-            b'K' => 12,
-            b'Q' => 11,
-            b'T' => 10,
-            b'J' => 1,
-            b => b & 0x0F,
-        })
+    let rules = Ruleset::jokers();
+    let get_hand_rank_2 = |hand: &[u8; 5]| rules.evaluate(hand).category;
+
+    assert_eq!(6, get_hand_rank_2(b"AAAAA"));
+    assert_eq!(5, get_hand_rank_2(b"AAAA2"));
+    assert_eq!(4, get_hand_rank_2(b"22333"));
+    assert_eq!(3, get_hand_rank_2(b"22234"));
+    assert_eq!(2, get_hand_rank_2(b"AA223"));
+    assert_eq!(1, get_hand_rank_2(b"AA234"));
+    assert_eq!(0, get_hand_rank_2(b"A2345"));
+
+    assert_eq!(5, get_hand_rank_2(b"223JJ")); // 4 of a kind
+    assert_eq!(4, get_hand_rank_2(b"2233J")); // full house
+    assert_eq!(3, get_hand_rank_2(b"234JJ")); // 3 of a kind
+    assert_eq!(1, get_hand_rank_2(b"2345J")); // pair
+    assert_eq!(6, get_hand_rank_2(b"JJJJJ")); // 5 of a kind
+}
+
+#[test]
+fn test_custom_ruleset() {
+    // deuces and jokers are wild, aces are low
+    let rules = Ruleset {
+        card_order: b"J2A3456789TQK".to_vec(),
+        wildcards: b"J2".to_vec(),
+        ..Ruleset::jokers()
+    };
+
+    let hand = rules.evaluate(b"J2K3K");
+    assert_eq!("four of a kind", rules.categories[hand.category].name);
+    assert_eq!(b"KKK3K".to_vec(), hand.effective);
+
+    assert!(rules.evaluate(b"A3456") < rules.evaluate(b"33456"));
+    assert!(rules.evaluate(b"AAAAJ") < rules.evaluate(b"KKKKK"));
+    assert!(rules.evaluate(b"AQQQQ") < rules.evaluate(b"2QQQQ"));
+
+    // six-card hands where two triples beat four of a kind
+    let mut categories = Ruleset::poker_categories();
+    categories.insert(6, HandCategory::new("double triple", &[3, 3]));
+    let rules = Ruleset {
+        hand_size: 6,
+        categories,
+        ..Ruleset::jokers()
     };
+    let hand = rules.evaluate(b"AAKKQJ");
+    assert_eq!("full house", rules.categories[hand.category].name);
+    let hand = rules.evaluate(b"AAAKKJ");
+    assert_eq!("double triple", rules.categories[hand.category].name);
+    assert_eq!(b"AAAKKK".to_vec(), hand.effective);
+}
+
+#[test]
+fn test_explain_winnings() {
+    let explanation = explain_winnings(Day::INPUT_SAMPLE, &Ruleset::jokers());
+    let total = explanation.iter().map(|e| e.rank * e.bid).sum::<usize>();
+    assert_eq!(5905, total);
+
+    let ranked = explanation
+        .iter()
+        .map(|e| (e.hand, e.category, e.effective.as_slice()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (b"32T3K".as_ref(), "one pair", b"32T3K".as_ref()),
+            (b"KK677", "two pair", b"KK677"),
+            (b"T55J5", "four of a kind", b"T5555"),
+            (b"QQQJA", "four of a kind", b"QQQQA"),
+            (b"KTJJT", "four of a kind", b"KTTTT"),
+        ],
+        ranked
+    );
+
+    assert_eq!(None, explanation[0].vs_lower);
+    assert_eq!(Some(TieBreak::Category), explanation[0].vs_higher);
+    assert_eq!(Some(TieBreak::Card(0)), explanation[2].vs_higher);
+    assert_eq!(Some(TieBreak::Card(0)), explanation[3].vs_higher);
+    assert_eq!(None, explanation[4].vs_higher);
+
+    assert_eq!(
+        "#3    T55J5 -> T5555 four of a kind  bid  684 =    2052 | vs lower: category | vs higher: card 1 (T)",
+        explanation[2].to_string()
+    );
+}
+
+#[test]
+fn test_repeated_hand() {
+    // both copies of the hand keep their bid, in input order
+    let input = "32T3K 765\nKK677 28\n32T3K 10";
+    assert_eq!(
+        765 + 2 * 10 + 3 * 28,
+        total_winnings(input, &Ruleset::standard())
+    );
 
-    assert_eq!(6, get_hand_rank_2(map(*b"AAAAA")));
-    assert_eq!(5, get_hand_rank_2(map(*b"AAAA2")));
-    assert_eq!(4, get_hand_rank_2(map(*b"22333")));
-    assert_eq!(3, get_hand_rank_2(map(*b"22234")));
-    assert_eq!(2, get_hand_rank_2(map(*b"AA223")));
-    assert_eq!(1, get_hand_rank_2(map(*b"AA234")));
-    assert_eq!(0, get_hand_rank_2(map(*b"A2345")));
-
-    assert_eq!(5, get_hand_rank_2(map(*b"223JJ"))); // 4 of a kind
-    assert_eq!(4, get_hand_rank_2(map(*b"2233J"))); // full house
-    assert_eq!(3, get_hand_rank_2(map(*b"234JJ"))); // 3 of a kind
-    assert_eq!(1, get_hand_rank_2(map(*b"2345J"))); // pair
+    let explanation = explain_winnings(input, &Ruleset::standard());
+    let total = explanation.iter().map(|e| e.rank * e.bid).sum::<usize>();
+    assert_eq!(total_winnings(input, &Ruleset::standard()), total);
+    assert_eq!(Some(TieBreak::Equal), explanation[0].vs_higher);
 }
 
 #[test]
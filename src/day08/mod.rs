@@ -1,5 +1,7 @@
 #![allow(clippy::naive_bytecount)]
 
+use std::fmt::Write;
+
 use super::*;
 
 pub struct Day;
@@ -10,36 +12,20 @@ impl SolutionSilver<usize> for Day {
     const INPUT_REAL: &'static str = include_str!("input_real.txt");
 
     fn calculate_silver(input: &str) -> usize {
-        let mut lines = input.lines();
-        let instructions = lines.next().unwrap();
-        _ = lines.next().unwrap();
-
-        let nodes = lines
-            .map(|l| {
-                let node: [u8; 3] = (&l.as_bytes()[..3]).try_into().unwrap();
-                let left: [u8; 3] = (&l.as_bytes()[7..10]).try_into().unwrap();
-                let right: [u8; 3] = (&l.as_bytes()[12..15]).try_into().unwrap();
+        let network = Network::parse(input);
+        let start = network.find("AAA").unwrap();
+        let end = network.find("ZZZ").unwrap();
 
-                (node, (left, right))
-            })
-            .collect::<ahash::AHashMap<_, _>>();
-
-        let mut current = *b"AAA";
+        let mut current = start;
         (0..)
-            .find(|_| {
-                if current == *b"ZZZ" {
+            .find(|&step| {
+                if current == end {
                     return true;
                 }
-                for instruction in instructions.bytes() {
-                    current = match instruction {
-                        b'L' => nodes[&current].0,
-                        _ => nodes[&current].1,
-                    };
-                }
+                current = network.step(current, step);
                 false
             })
             .unwrap()
-            * instructions.len()
     }
 }
 
@@ -47,68 +33,360 @@ impl SolutionGold<usize, usize> for Day {
     const INPUT_SAMPLE_GOLD: &'static str = include_str!("input_sample_gold.txt");
 
     fn calculate_gold(input: &str) -> usize {
+        let network = Network::parse(input);
+        network
+            .ghost_walk()
+            .expect("ghosts never meet on end nodes")
+    }
+}
+
+/// The parsed map, with nodes stored by index.
+pub struct Network<'a> {
+    pub instructions: &'a [u8],
+    pub nodes: Vec<Node<'a>>,
+}
+
+pub struct Node<'a> {
+    pub name: &'a str,
+    pub left: usize,
+    pub right: usize,
+}
+
+/// The path of a single ghost, which always ends up in a loop of (node, instruction index) states.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GhostCycle {
+    /// Amount of steps before the ghost enters the cycle.
+    pub lead_in: usize,
+    /// Amount of steps in the cycle.
+    pub length: usize,
+    /// Steps before the cycle on which the ghost is on an end node.
+    pub lead_in_hits: Vec<usize>,
+    /// Steps in the first iteration of the cycle on which the ghost is on an end node. These
+    /// repeat every `length` steps.
+    pub cycle_hits: Vec<usize>,
+}
+
+impl<'a> Network<'a> {
+    pub fn parse(input: &'a str) -> Self {
         let mut lines = input.lines();
-        let instructions = lines.next().unwrap();
+        let instructions = lines.next().unwrap().as_bytes();
         _ = lines.next().unwrap();
 
-        let nodes = lines
+        let lines = lines
             .map(|l| {
-                let node: [u8; 3] = (&l.as_bytes()[..3]).try_into().unwrap();
-                let left: [u8; 3] = (&l.as_bytes()[7..10]).try_into().unwrap();
-                let right: [u8; 3] = (&l.as_bytes()[12..15]).try_into().unwrap();
+                let (node, targets) = l.split_once(" = ").unwrap();
+                let targets = targets.trim_start_matches('(').trim_end_matches(')');
+                let (left, right) = targets.split_once(", ").unwrap();
 
-                (node, (left, right))
+                (node, left, right)
             })
+            .collect::<Vec<_>>();
+
+        let indices = lines
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _, _))| (*name, i))
             .collect::<ahash::AHashMap<_, _>>();
 
-        nodes
-            .keys()
-            .filter(|k| k[2] == b'A')
-            .cloned()
-            .map(|node| {
-                let mut current = node;
-                (0..)
-                    .find(|_| {
-                        if current[2] == b'Z' {
-                            return true;
-                        }
-                        for instruction in instructions.bytes() {
-                            current = match instruction {
-                                b'L' => nodes[&current].0,
-                                _ => nodes[&current].1,
-                            };
-                        }
-                        false
-                    })
-                    .unwrap()
+        let nodes = lines
+            .iter()
+            .map(|(name, left, right)| Node {
+                name,
+                left: indices[left],
+                right: indices[right],
             })
-            .reduce(lcm)
-            .unwrap()
-            * instructions.len()
+            .collect();
+
+        Self {
+            instructions,
+            nodes,
+        }
     }
-}
 
-// https://www.hackertouch.com/least-common-multiple-in-rust.html
-fn lcm(first: usize, second: usize) -> usize {
-    first * second / gcd(first, second)
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|n| n.name == name)
+    }
+
+    /// Returns the node reached from `node` on the given step.
+    pub fn step(&self, node: usize, step: usize) -> usize {
+        match self.instructions[step % self.instructions.len()] {
+            b'L' => self.nodes[node].left,
+            _ => self.nodes[node].right,
+        }
+    }
+
+    /// Follows a ghost from `start` until its state repeats.
+    pub fn ghost_cycle(&self, start: usize) -> GhostCycle {
+        let instruction_count = self.instructions.len();
+        let is_end = |node: usize| self.nodes[node].is_ghost_end();
+
+        let mut first_seen = vec![usize::MAX; self.nodes.len() * instruction_count];
+        let mut hits = vec![];
+        let mut current = start;
+        let mut step = 0;
+        loop {
+            let state = current * instruction_count + step % instruction_count;
+            if first_seen[state] != usize::MAX {
+                let lead_in = first_seen[state];
+                let (lead_in_hits, cycle_hits) = hits.iter().partition(|&&hit| hit < lead_in);
+                return GhostCycle {
+                    lead_in,
+                    length: step - lead_in,
+                    lead_in_hits,
+                    cycle_hits,
+                };
+            }
+            first_seen[state] = step;
+
+            if is_end(current) {
+                hits.push(step);
+            }
+            current = self.step(current, step);
+            step += 1;
+        }
+    }
+
+    /// Returns the first step on which every ghost starting on an `..A` node is on a `..Z` node,
+    /// or `None` if that never happens.
+    pub fn ghost_walk(&self) -> Option<usize> {
+        let cycles = (0..self.nodes.len())
+            .filter(|&n| self.nodes[n].is_ghost_start())
+            .map(|n| self.ghost_cycle(n))
+            .collect::<Vec<_>>();
+
+        first_common_hit(&cycles)
+    }
+
+    /// Exports the network as a Graphviz DOT graph. Ghost start and end nodes are highlighted and
+    /// the edges each ghost keeps taking once it is in its cycle are coloured per ghost.
+    pub fn to_dot(&self) -> String {
+        const GHOST_COLORS: [&str; 6] = ["red", "blue", "darkgreen", "orange", "purple", "brown"];
+
+        // (from, to) -> colour of the ghost whose cycle uses this edge
+        let mut cycle_edges = ahash::AHashMap::new();
+        let starts = (0..self.nodes.len()).filter(|&n| self.nodes[n].is_ghost_start());
+        for (ghost, start) in starts.enumerate() {
+            let cycle = self.ghost_cycle(start);
+            let color = GHOST_COLORS[ghost % GHOST_COLORS.len()];
+
+            let mut current = start;
+            for step in 0..(cycle.lead_in + cycle.length) {
+                let next = self.step(current, step);
+                if step >= cycle.lead_in {
+                    cycle_edges.entry((current, next)).or_insert(color);
+                }
+                current = next;
+            }
+        }
+
+        let mut dot = String::new();
+        _ = writeln!(dot, "digraph network {{");
+        for node in &self.nodes {
+            let style = if node.is_ghost_start() {
+                ", style=filled, fillcolor=palegreen"
+            } else if node.is_ghost_end() {
+                ", style=filled, fillcolor=lightcoral"
+            } else {
+                ""
+            };
+            _ = writeln!(dot, "    \"{0}\" [label=\"{0}\"{style}];", node.name);
+        }
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let edges = if node.left == node.right {
+                vec![(node.left, "LR")]
+            } else {
+                vec![(node.left, "L"), (node.right, "R")]
+            };
+            for (target, label) in edges {
+                let style = cycle_edges
+                    .get(&(i, target))
+                    .map(|color| format!(", color={color}, penwidth=2"))
+                    .unwrap_or_default();
+                _ = writeln!(
+                    dot,
+                    "    \"{}\" -> \"{}\" [label=\"{label}\"{style}];",
+                    node.name, self.nodes[target].name,
+                );
+            }
+        }
+        _ = writeln!(dot, "}}");
+
+        dot
+    }
 }
 
-fn gcd(first: usize, second: usize) -> usize {
-    let mut max = first;
-    let mut min = second;
-    if min > max {
-        std::mem::swap(&mut max, &mut min);
+impl Node<'_> {
+    pub fn is_ghost_start(&self) -> bool {
+        self.name.ends_with('A')
     }
 
-    loop {
-        let res = max % min;
-        if res == 0 {
-            return min;
+    pub fn is_ghost_end(&self) -> bool {
+        self.name.ends_with('Z')
+    }
+}
+
+impl GhostCycle {
+    pub fn is_hit(&self, step: usize) -> bool {
+        if step < self.lead_in {
+            self.lead_in_hits.contains(&step)
+        } else {
+            let offset = (step - self.lead_in) % self.length;
+            self.cycle_hits.contains(&(self.lead_in + offset))
         }
+    }
+}
+
+/// Finds the first step that is a hit for every ghost.
+pub fn first_common_hit(cycles: &[GhostCycle]) -> Option<usize> {
+    // any hit before all ghosts are in their cycle must be one of the lead-in hits
+    let all_cycling = cycles.iter().map(|c| c.lead_in).max()?;
+    let mut lead_in_hits = cycles
+        .iter()
+        .flat_map(|c| c.lead_in_hits.iter().copied())
+        .collect::<Vec<_>>();
+    lead_in_hits.sort_unstable();
+    if let Some(hit) = lead_in_hits
+        .into_iter()
+        .find(|&hit| cycles.iter().all(|c| c.is_hit(hit)))
+    {
+        return Some(hit);
+    }
+
+    // after that, every ghost's hits are periodic. combine the residues of all ghosts.
+    let mut residues = vec![(0i128, 1i128)];
+    for cycle in cycles {
+        let length = cycle.length as i128;
+        let mut combined = residues
+            .iter()
+            .flat_map(|&(a, m)| {
+                cycle
+                    .cycle_hits
+                    .iter()
+                    .filter_map(move |&hit| crt((a, m), (hit as i128 % length, length)))
+            })
+            .collect::<Vec<_>>();
+        combined.sort_unstable();
+        combined.dedup();
+        residues = combined;
+    }
+
+    let all_cycling = all_cycling as i128;
+    residues
+        .into_iter()
+        .map(|(a, m)| {
+            // smallest number >= all_cycling that is equal to `a` modulo `m`
+            all_cycling + (a - all_cycling).rem_euclid(m)
+        })
+        .min()
+        .and_then(|step| usize::try_from(step).ok())
+}
 
-        max = min;
-        min = res;
+/// Combines `x = a1 (mod m1)` and `x = a2 (mod m2)` into `x = a (mod lcm(m1, m2))`, for moduli that
+/// are not necessarily coprime. Returns `None` if there is no solution.
+fn crt((a1, m1): (i128, i128), (a2, m2): (i128, i128)) -> Option<(i128, i128)> {
+    let (g, p, _) = extended_gcd(m1, m2);
+    if (a2 - a1) % g != 0 {
+        return None;
     }
+
+    let m2_g = m2 / g;
+    let k = ((a2 - a1) / g % m2_g * p % m2_g).rem_euclid(m2_g);
+    let modulus = m1 * m2_g;
+    Some(((a1 + m1 * k).rem_euclid(modulus), modulus))
+}
+
+/// Returns `(g, x, y)` such that `a*x + b*y = g = gcd(a, b)`.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - a / b * y)
+    }
+}
+
+#[test]
+fn test_ghost_cycle() {
+    let network = Network::parse(Day::INPUT_SAMPLE_GOLD);
+    let cycle = network.ghost_cycle(network.find("22A").unwrap());
+    assert_eq!(
+        GhostCycle {
+            lead_in: 1,
+            length: 6,
+            lead_in_hits: vec![],
+            cycle_hits: vec![3, 6],
+        },
+        cycle
+    );
+    assert!(!cycle.is_hit(0));
+    assert!(cycle.is_hit(9));
+    assert!(cycle.is_hit(12));
+    assert!(!cycle.is_hit(13));
+}
+
+#[test]
+fn test_first_common_hit_unaligned() {
+    let cycle = |lead_in, length, lead_in_hits: &[usize], cycle_hits: &[usize]| GhostCycle {
+        lead_in,
+        length,
+        lead_in_hits: lead_in_hits.to_vec(),
+        cycle_hits: cycle_hits.to_vec(),
+    };
+
+    // hits in the lead-in
+    let cycles = [cycle(5, 3, &[2], &[6]), cycle(0, 2, &[], &[0])];
+    assert_eq!(Some(2), first_common_hit(&cycles));
+
+    // cycle lengths that share a factor, with offsets
+    let cycles = [cycle(3, 4, &[], &[5]), cycle(1, 6, &[], &[3])];
+    assert_eq!(Some(9), first_common_hit(&cycles));
+
+    // multiple hits per cycle
+    let cycles = [cycle(0, 10, &[], &[3, 7]), cycle(0, 15, &[], &[2, 12])];
+    assert_eq!(Some(17), first_common_hit(&cycles));
+
+    // even and odd hits with even cycle lengths never line up
+    let cycles = [cycle(0, 4, &[], &[1]), cycle(0, 6, &[], &[2])];
+    assert_eq!(None, first_common_hit(&cycles));
+}
+
+#[test]
+fn test_ghost_walk_no_solution() {
+    let input = "L\n\n11A = (11Z, 11Z)\n11Z = (11A, 11A)\n22A = (22Z, 22Z)\n22Z = (22Z, 22Z)\n";
+    let network = Network::parse(input);
+    assert_eq!(Some(1), network.ghost_walk());
+
+    let input = "L\n\n11A = (11Z, 11Z)\n11Z = (11A, 11A)\n22A = (22B, 22B)\n22B = (22Z, 22Z)\n22Z = (22B, 22B)\n";
+    let network = Network::parse(input);
+    assert_eq!(None, network.ghost_walk());
+}
+
+#[test]
+fn test_long_node_names() {
+    let input = "RL\n\nSTART = (MIDDLE, END)\nMIDDLE = (END, START)\nEND = (END, END)\n";
+    let network = Network::parse(input);
+    assert_eq!(3, network.nodes.len());
+    assert_eq!("MIDDLE", network.nodes[network.nodes[0].left].name);
+    assert_eq!(Some(2), network.find("END"));
+    assert_eq!(2, network.step(0, 0));
+}
+
+#[test]
+fn test_to_dot() {
+    let network = Network::parse(Day::INPUT_SAMPLE_GOLD);
+    let dot = network.to_dot();
+
+    assert!(dot.starts_with("digraph network {\n"));
+    assert!(dot.contains("    \"11A\" [label=\"11A\", style=filled, fillcolor=palegreen];\n"));
+    assert!(dot.contains("    \"22Z\" [label=\"22Z\", style=filled, fillcolor=lightcoral];\n"));
+    assert!(dot.contains("    \"XXX\" [label=\"XXX\"];\n"));
+
+    // 11A leaves its cycle immediately, 22B -> 22C is part of the second ghost's cycle
+    assert!(dot.contains("    \"11A\" -> \"11B\" [label=\"L\"];\n"));
+    assert!(dot.contains("    \"11B\" -> \"11Z\" [label=\"R\", color=red, penwidth=2];\n"));
+    assert!(dot.contains("    \"22B\" -> \"22C\" [label=\"LR\", color=blue, penwidth=2];\n"));
+    assert!(dot.ends_with("}\n"));
 }
 
 #[test]
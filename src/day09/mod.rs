@@ -1,5 +1,3 @@
-use std::cell::Cell;
-
 use super::*;
 
 pub struct Day;
@@ -10,73 +8,230 @@ impl SolutionSilver<usize> for Day {
     const INPUT_REAL: &'static str = include_str!("input_real.txt");
 
     fn calculate_silver(input: &str) -> usize {
-        let mut buffer = vec![];
-        input
-            .lines()
-            .map(|l| {
-                // re-usable buffer for cache efficiency and reducing heap allocs
-                buffer.clear();
-                buffer.extend(l.split(' ').map(|n| n.parse::<isize>().unwrap()));
-
-                // the amount of rows we currently have
-                let mut depth = 0;
-                let len = buffer.len();
-                loop {
-                    let subslice = &mut buffer[..(len - depth)];
-                    if subslice.iter().skip(1).all(|w| subslice[0] == *w) {
-                        // all good
-                        break;
-                    }
-
-                    // hack described by the `.windows(_)` docs, to emulate `.windows_mut(_)`
-                    let buffer_cells = Cell::from_mut(subslice).as_slice_of_cells();
-                    buffer_cells.windows(2).for_each(|w| {
-                        w[0].set(w[1].get() - w[0].get());
-                    });
-
-                    depth += 1;
-                }
-
-                let start_index = buffer.len() - 1 - depth;
-                buffer[start_index..].iter().sum::<isize>()
+        parse_histories(input)
+            .map(|history| {
+                let fit = HistoryFit::new(&history);
+                fit.predict(history.len() as i128).unwrap()
             })
-            .sum::<isize>() as usize
+            .sum::<i128>() as usize
     }
 }
 
 impl SolutionGold<usize, usize> for Day {
     fn calculate_gold(input: &str) -> usize {
-        let mut buffer = vec![];
-        input
-            .lines()
-            .map(|l| {
-                buffer.clear();
-                buffer.extend(l.split(' ').map(|n| n.parse::<isize>().unwrap()));
-
-                let mut depth = 0;
-                loop {
-                    let subslice = &mut buffer[depth..];
-                    if subslice.iter().skip(1).all(|w| subslice[0] == *w) {
-                        break;
-                    }
-
-                    let buffer_cells = Cell::from_mut(subslice).as_slice_of_cells();
-                    buffer_cells.windows(2).rev().for_each(|w| {
-                        w[1].set(w[1].get() - w[0].get());
-                    });
-
-                    depth += 1;
+        parse_histories(input)
+            .map(|history| HistoryFit::new(&history).predict(-1).unwrap())
+            .sum::<i128>() as usize
+    }
+}
+
+fn parse_histories(input: &str) -> impl Iterator<Item = Vec<i64>> + '_ {
+    input
+        .lines()
+        .map(|l| l.split(' ').map(|n| n.parse::<i64>().unwrap()).collect())
+}
+
+/// A history fitted exactly as a polynomial, where the value at index `k` is
+/// `sum(differences[j] * binomial(k, j))` (Newton's forward difference formula).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryFit {
+    /// The first value of each row of differences, up to and including the first constant row.
+    pub differences: Vec<i128>,
+    /// Whether the differences reached a constant row while it still had more than one value. If
+    /// this is false, the polynomial passes through all values but the history didn't contain
+    /// enough values to confirm the degree.
+    pub converged: bool,
+}
+
+/// An exact fraction, always stored with a positive denominator and in lowest terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    pub numerator: i128,
+    pub denominator: i128,
+}
+
+impl HistoryFit {
+    pub fn new(history: &[i64]) -> Self {
+        assert!(!history.is_empty(), "empty history");
+
+        let mut row = history.iter().map(|&v| i128::from(v)).collect::<Vec<_>>();
+        let mut differences = vec![];
+        loop {
+            differences.push(row[0]);
+            if row.iter().all(|v| *v == row[0]) {
+                return Self {
+                    differences,
+                    converged: row.len() > 1,
+                };
+            }
+
+            row = row.windows(2).map(|w| w[1] - w[0]).collect();
+        }
+    }
+
+    pub fn degree(&self) -> usize {
+        self.differences.len() - 1
+    }
+
+    /// Returns the value at index `k`, where index 0 is the first value of the history. Negative
+    /// indices extrapolate backwards. Returns `None` if the value doesn't fit in an `i128`.
+    pub fn predict(&self, k: i128) -> Option<i128> {
+        let mut binomial = 1i128;
+        let mut total = 0i128;
+        for (j, difference) in self.differences.iter().enumerate() {
+            if j > 0 {
+                binomial = binomial_step(binomial, k, j as i128)?;
+            }
+            total = total.checked_add(difference.checked_mul(binomial)?)?;
+        }
+        Some(total)
+    }
+
+    /// Returns the coefficients of the polynomial in the power basis, starting with the constant
+    /// term. Returns `None` if an intermediate value doesn't fit in an `i128`.
+    pub fn coefficients(&self) -> Option<Vec<Rational>> {
+        let mut coefficients = vec![Rational::from(0); self.differences.len()];
+
+        // the falling factorial x(x-1)...(x-j+1) in the power basis
+        let mut falling = vec![1i128];
+        let mut factorial = 1i128;
+        for (j, difference) in self.differences.iter().enumerate() {
+            if j > 0 {
+                let j = j as i128;
+                factorial = factorial.checked_mul(j)?;
+
+                // multiply by (x - (j - 1))
+                let mut next = vec![0i128; falling.len() + 1];
+                for (power, coefficient) in falling.iter().enumerate() {
+                    next[power + 1] = next[power + 1].checked_add(*coefficient)?;
+                    next[power] = next[power].checked_sub(coefficient.checked_mul(j - 1)?)?;
                 }
+                falling = next;
+            }
 
-                let start_index = depth + 1;
-                buffer[..start_index]
-                    .iter()
-                    .enumerate()
-                    .map(|(i, v)| if i % 2 == 0 { *v } else { -*v })
-                    .sum::<isize>()
-            })
-            .sum::<isize>() as usize
+            for (power, coefficient) in falling.iter().enumerate() {
+                let term = Rational::new(difference.checked_mul(*coefficient)?, factorial);
+                coefficients[power] = coefficients[power].checked_add(term)?;
+            }
+        }
+
+        Some(coefficients)
+    }
+}
+
+/// Calculates `binomial(k, j)` from `binomial(k, j - 1)`. This works for negative `k` too.
+fn binomial_step(previous: i128, k: i128, j: i128) -> Option<i128> {
+    // `previous * (k - j + 1)` is always divisible by `j`. split the division over both factors
+    // so the intermediate value can't overflow if the result doesn't.
+    let g = gcd(previous, j);
+    let factor = k.checked_sub(j - 1)?;
+    (previous / g).checked_mul(factor / (j / g))
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+impl Rational {
+    pub fn new(numerator: i128, denominator: i128) -> Self {
+        assert_ne!(0, denominator, "zero denominator");
+        let g = gcd(numerator, denominator).max(1) * denominator.signum();
+        Self {
+            numerator: numerator / g,
+            denominator: denominator / g,
+        }
     }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        let g = gcd(self.denominator, other.denominator);
+        let denominator = (self.denominator / g).checked_mul(other.denominator)?;
+        let numerator = self
+            .numerator
+            .checked_mul(other.denominator / g)?
+            .checked_add(other.numerator.checked_mul(self.denominator / g)?)?;
+        Some(Self::new(numerator, denominator))
+    }
+}
+
+impl From<i128> for Rational {
+    fn from(value: i128) -> Self {
+        Self::new(value, 1)
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+#[test]
+fn test_history_fit() {
+    let fit = HistoryFit::new(&[0, 3, 6, 9, 12, 15]);
+    assert_eq!(1, fit.degree());
+    assert!(fit.converged);
+    assert_eq!(Some(18), fit.predict(6));
+    assert_eq!(Some(-3), fit.predict(-1));
+    assert_eq!(
+        Some(3_000_000_000_000_000_000),
+        fit.predict(1_000_000_000_000_000_000)
+    );
+
+    let fit = HistoryFit::new(&[1, 3, 6, 10, 15, 21]);
+    assert_eq!(2, fit.degree());
+    assert_eq!(
+        Some(vec![
+            Rational::new(1, 1),
+            Rational::new(3, 2),
+            Rational::new(1, 2)
+        ]),
+        fit.coefficients()
+    );
+    // (k + 1)(k + 2) / 2
+    let k = 10i128.pow(18);
+    assert_eq!(Some((k + 1) * (k + 2) / 2), fit.predict(k));
+    assert_eq!(Some(0), fit.predict(-1));
+    assert_eq!(Some(0), fit.predict(-2));
+    assert_eq!(Some(1), fit.predict(-3));
+
+    let fit = HistoryFit::new(&[10, 13, 16, 21, 30, 45]);
+    assert_eq!(3, fit.degree());
+    assert_eq!(Some(68), fit.predict(6));
+    assert_eq!(Some(5), fit.predict(-1));
+    assert_eq!(None, fit.predict(10i128.pow(18)));
+    let coefficients = fit.coefficients().unwrap();
+    assert_eq!(
+        "10, 11/3, -1, 1/3",
+        coefficients
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+}
+
+#[test]
+fn test_history_fit_not_converged() {
+    let fit = HistoryFit::new(&[1, 2, 4, 8, 16]);
+    assert!(!fit.converged);
+    assert_eq!(4, fit.degree());
+    assert_eq!(Some(31), fit.predict(5));
+
+    let fit = HistoryFit::new(&[7]);
+    assert!(!fit.converged);
+    assert_eq!(0, fit.degree());
+
+    let fit = HistoryFit::new(&[7, 7]);
+    assert!(fit.converged);
+    assert_eq!(Some(vec![Rational::from(7)]), fit.coefficients());
 }
 
 #[test]
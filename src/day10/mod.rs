@@ -10,81 +10,11 @@ impl SolutionSilver<usize> for Day {
     const INPUT_REAL: &'static str = include_str!("input_real.txt");
 
     fn calculate_silver(input: &str) -> usize {
-        let width = input.lines().next().unwrap().len();
-        let height = input.lines().count();
+        let (grid, width, height) = parse_grid(input);
+        let main_loop = MainLoop::find(&grid, width, height).unwrap();
 
-        let grid = input
-            .lines()
-            .flat_map(|line| line.as_bytes())
-            .collect::<Vec<_>>();
-
-        let start_position = grid.iter().position(|&c| *c == b'S').unwrap();
-
-        let mut distances = vec![usize::MAX; width * height];
-
-        let mut queue = VecDeque::new();
-        queue.push_back((start_position, 0));
-        distances[start_position] = 0;
-
-        while let Some((position, distance)) = queue.pop_front() {
-            let x = position % width;
-            let y = position / width;
-
-            // left
-            if x > 0 {
-                let target_position = position - 1;
-                if matches!(grid[position], b'S' | b'-' | b'J' | b'7')
-                    && matches!(grid[target_position], b'-' | b'L' | b'F')
-                    && distances[target_position] > distance + 1
-                {
-                    distances[target_position] = distance + 1;
-                    queue.push_back((target_position, distance + 1));
-                }
-            }
-
-            // right
-            if x < width - 1 {
-                let target_position = position + 1;
-                if matches!(grid[position], b'S' | b'-' | b'L' | b'F')
-                    && matches!(grid[target_position], b'-' | b'J' | b'7')
-                    && distances[target_position] > distance + 1
-                {
-                    distances[target_position] = distance + 1;
-                    queue.push_back((target_position, distance + 1));
-                }
-            }
-
-            // top
-            if y > 0 {
-                let target_position = position - width;
-                if matches!(grid[position], b'S' | b'|' | b'L' | b'J')
-                    && matches!(grid[target_position], b'|' | b'F' | b'7')
-                    && distances[target_position] > distance + 1
-                {
-                    distances[target_position] = distance + 1;
-                    queue.push_back((target_position, distance + 1));
-                }
-            }
-
-            // bottom
-            if y < height - 1 {
-                let target_position = position + width;
-                if matches!(grid[position], b'S' | b'|' | b'F' | b'7')
-                    && matches!(grid[target_position], b'|' | b'L' | b'J')
-                    && distances[target_position] > distance + 1
-                {
-                    distances[target_position] = distance + 1;
-                    queue.push_back((target_position, distance + 1));
-                }
-            }
-        }
-
-        distances
-            .iter()
-            .filter(|&&d| d != usize::MAX)
-            .max()
-            .unwrap()
-            .to_owned()
+        // the furthest point is halfway along the loop
+        main_loop.vertices.len() / 2
     }
 }
 
@@ -92,73 +22,69 @@ impl SolutionGold<usize, usize> for Day {
     const INPUT_SAMPLE_GOLD: &'static str = include_str!("input_sample_gold3.txt");
 
     fn calculate_gold(input: &str) -> usize {
-        let width = input.lines().next().unwrap().len();
-        let height = input.lines().count();
-
-        let grid = input
-            .lines()
-            .flat_map(|line| line.as_bytes())
-            .collect::<Vec<_>>();
-
-        let start_position = grid.iter().position(|&c| *c == b'S').unwrap();
-
-        let mut the_loop = vec![false; width * height];
+        PipeMaze::analyze(input)
+            .unwrap()
+            .tiles
+            .iter()
+            .filter(|t| **t == Tile::Enclosed)
+            .count()
+    }
+}
 
-        let mut queue = VecDeque::new();
-        queue.push_back(start_position);
-        the_loop[start_position] = true;
+/// A pipe maze where every tile has been classified.
+pub struct PipeMaze {
+    pub width: usize,
+    pub height: usize,
+    /// The tiles of the maze, with the start tile replaced by the pipe it covers.
+    pub grid: Vec<u8>,
+    pub tiles: Vec<Tile>,
+    pub main_loop: MainLoop,
+}
 
-        while let Some(position) = queue.pop_front() {
-            let x = position % width;
-            let y = position / width;
+/// The loop that goes through the start tile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MainLoop {
+    /// The pipe under the start tile.
+    pub start_tile: u8,
+    /// The `(x, y)` coordinates of every tile in the loop in walking order, starting at the start
+    /// tile.
+    pub vertices: Vec<(usize, usize)>,
+}
 
-            // left
-            if x > 0 {
-                let target_position = position - 1;
-                if matches!(grid[position], b'S' | b'-' | b'J' | b'7')
-                    && matches!(grid[target_position], b'-' | b'L' | b'F')
-                    && !the_loop[target_position]
-                {
-                    the_loop[target_position] = true;
-                    queue.push_back(target_position);
-                }
-            }
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MazeError {
+    NoStart,
+    MultipleStarts,
+    /// The start tile must connect to exactly 2 neighbours, but these were found.
+    AmbiguousStart {
+        connected: Vec<(usize, usize)>,
+    },
+    /// Following the loop led to a tile that doesn't connect back to the previous one.
+    BrokenLoop {
+        at: (usize, usize),
+    },
+}
 
-            // right
-            if x < width - 1 {
-                let target_position = position + 1;
-                if matches!(grid[position], b'S' | b'-' | b'L' | b'F')
-                    && matches!(grid[target_position], b'-' | b'J' | b'7')
-                    && !the_loop[target_position]
-                {
-                    the_loop[target_position] = true;
-                    queue.push_back(target_position);
-                }
-            }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    /// Part of the main loop.
+    Loop,
+    /// Not part of the main loop, but enclosed by it.
+    Enclosed,
+    /// Not part of the main loop, and not enclosed by it.
+    Outside,
+}
 
-            // top
-            if y > 0 {
-                let target_position = position - width;
-                if matches!(grid[position], b'S' | b'|' | b'L' | b'J')
-                    && matches!(grid[target_position], b'|' | b'F' | b'7')
-                    && !the_loop[target_position]
-                {
-                    the_loop[target_position] = true;
-                    queue.push_back(target_position);
-                }
-            }
+impl PipeMaze {
+    pub fn analyze(input: &str) -> Result<Self, MazeError> {
+        let (mut grid, width, height) = parse_grid(input);
+        let main_loop = MainLoop::find(&grid, width, height)?;
+        let (start_x, start_y) = main_loop.vertices[0];
+        grid[start_x + start_y * width] = main_loop.start_tile;
 
-            // bottom
-            if y < height - 1 {
-                let target_position = position + width;
-                if matches!(grid[position], b'S' | b'|' | b'F' | b'7')
-                    && matches!(grid[target_position], b'|' | b'L' | b'J')
-                    && !the_loop[target_position]
-                {
-                    the_loop[target_position] = true;
-                    queue.push_back(target_position);
-                }
-            }
+        let mut the_loop = vec![false; width * height];
+        for (x, y) in &main_loop.vertices {
+            the_loop[x + y * width] = true;
         }
 
         // flood-fill the grid to find which tiles are within the loop
@@ -224,7 +150,7 @@ impl SolutionGold<usize, usize> for Day {
             area_outside_loop[pos] = true;
         }
 
-        let mut loop_count = 0;
+        let mut enclosed = vec![false; width * height];
         let mut grid_positions_within_loop = HashSet::<usize>::new();
         let mut grid_positions_outside_loop = HashSet::<usize>::new();
         for position in 0..grid.len() {
@@ -326,7 +252,7 @@ impl SolutionGold<usize, usize> for Day {
 
             if !escaped {
                 // we found a loop
-                loop_count += 1;
+                enclosed[position] = true;
                 for grid_pos in visited
                     .iter()
                     .enumerate()
@@ -343,10 +269,331 @@ impl SolutionGold<usize, usize> for Day {
             }
         }
 
-        loop_count
+        let tiles = (0..grid.len())
+            .map(|position| {
+                if the_loop[position] {
+                    Tile::Loop
+                } else if enclosed[position] {
+                    Tile::Enclosed
+                } else {
+                    Tile::Outside
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            grid,
+            tiles,
+            main_loop,
+        })
+    }
+
+    /// Draws the maze using box-drawing characters for the main loop, `I` for enclosed tiles and
+    /// `O` for outside tiles. Optionally colours the tiles using ANSI escape codes.
+    pub fn render(&self, colored: bool) -> String {
+        const LOOP_COLOR: &str = "\x1b[1;33m";
+        const ENCLOSED_COLOR: &str = "\x1b[1;41m";
+        const OUTSIDE_COLOR: &str = "\x1b[2m";
+        const RESET: &str = "\x1b[0m";
+
+        let mut output = String::with_capacity(self.grid.len() * 2);
+        for y in 0..self.height {
+            let mut previous_tile = None;
+            for x in 0..self.width {
+                let position = x + y * self.width;
+                let tile = self.tiles[position];
+
+                if colored && previous_tile != Some(tile) {
+                    output.push_str(match tile {
+                        Tile::Loop => LOOP_COLOR,
+                        Tile::Enclosed => ENCLOSED_COLOR,
+                        Tile::Outside => OUTSIDE_COLOR,
+                    });
+                    previous_tile = Some(tile);
+                }
+
+                output.push(match tile {
+                    Tile::Loop => box_drawing_char(self.grid[position]),
+                    Tile::Enclosed => 'I',
+                    Tile::Outside => 'O',
+                });
+            }
+            if colored {
+                output.push_str(RESET);
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+
+    /// Exports the maze as a binary PPM image. Each tile is drawn as a 3x3 block of pixels, with
+    /// the pipes of the main loop drawn over the background colour of the tile.
+    pub fn to_ppm(&self) -> Vec<u8> {
+        const SCALE: usize = 3;
+        const LOOP_COLOR: [u8; 3] = [255, 200, 0];
+        const ENCLOSED_COLOR: [u8; 3] = [200, 40, 40];
+        const OUTSIDE_COLOR: [u8; 3] = [40, 40, 40];
+        const PIPE_BACKGROUND_COLOR: [u8; 3] = [0, 0, 0];
+
+        let image_width = self.width * SCALE;
+        let image_height = self.height * SCALE;
+        let mut pixels = vec![[0u8; 3]; image_width * image_height];
+
+        for position in 0..self.grid.len() {
+            let x = position % self.width;
+            let y = position / self.width;
+            let tile = self.tiles[position];
+
+            // which of the 3x3 pixels are part of the pipe
+            let mut pipe = [false; 9];
+            if tile == Tile::Loop {
+                let [left, right, top, bottom] = pipe_connections(self.grid[position]);
+                pipe[4] = true;
+                pipe[3] = left;
+                pipe[5] = right;
+                pipe[1] = top;
+                pipe[7] = bottom;
+            }
+
+            for (i, is_pipe) in pipe.iter().enumerate() {
+                let color = match tile {
+                    Tile::Loop if *is_pipe => LOOP_COLOR,
+                    Tile::Loop => PIPE_BACKGROUND_COLOR,
+                    Tile::Enclosed => ENCLOSED_COLOR,
+                    Tile::Outside => OUTSIDE_COLOR,
+                };
+                let pixel_x = x * SCALE + i % SCALE;
+                let pixel_y = y * SCALE + i / SCALE;
+                pixels[pixel_x + pixel_y * image_width] = color;
+            }
+        }
+
+        let mut output = format!("P6\n{image_width} {image_height}\n255\n").into_bytes();
+        output.extend(pixels.iter().flatten());
+        output
+    }
+}
+
+impl MainLoop {
+    /// Infers the pipe under the start tile and follows the loop through it.
+    pub fn find(grid: &[u8], width: usize, height: usize) -> Result<Self, MazeError> {
+        let mut starts = (0..grid.len()).filter(|&i| grid[i] == b'S');
+        let start = starts.next().ok_or(MazeError::NoStart)?;
+        if starts.next().is_some() {
+            return Err(MazeError::MultipleStarts);
+        }
+        let start = (start % width, start / width);
+
+        let neighbour = |(x, y): (usize, usize), direction: usize| {
+            let (dx, dy) = DIRECTIONS[direction];
+            let x = x.checked_add_signed(dx).filter(|x| *x < width)?;
+            let y = y.checked_add_signed(dy).filter(|y| *y < height)?;
+            Some((x, y))
+        };
+
+        // the start connects to every neighbour that has a pipe pointing back at it
+        let mut start_connections = [false; 4];
+        for direction in 0..4 {
+            if let Some((x, y)) = neighbour(start, direction) {
+                start_connections[direction] =
+                    pipe_connections(grid[x + y * width])[opposite(direction)];
+            }
+        }
+        if start_connections.iter().filter(|c| **c).count() != 2 {
+            let connected = (0..4)
+                .filter(|d| start_connections[*d])
+                .filter_map(|d| neighbour(start, d))
+                .collect();
+            return Err(MazeError::AmbiguousStart { connected });
+        }
+        let start_tile = pipe_from_connections(start_connections);
+
+        let mut vertices = vec![start];
+        let mut direction = start_connections.iter().position(|c| *c).unwrap();
+        let mut current = neighbour(start, direction).unwrap();
+        while current != start {
+            let connections = pipe_connections(grid[current.0 + current.1 * width]);
+            let came_from = opposite(direction);
+            if !connections[came_from] {
+                return Err(MazeError::BrokenLoop { at: current });
+            }
+
+            vertices.push(current);
+            direction = (0..4).find(|d| *d != came_from && connections[*d]).unwrap();
+            current = neighbour(current, direction).ok_or(MazeError::BrokenLoop { at: current })?;
+        }
+
+        Ok(Self {
+            start_tile,
+            vertices,
+        })
+    }
+}
+
+impl Display for MazeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoStart => write!(f, "the maze has no start tile"),
+            Self::MultipleStarts => write!(f, "the maze has more than one start tile"),
+            Self::AmbiguousStart { connected } => write!(
+                f,
+                "the start tile must connect to exactly 2 pipes, but connects to {}: {connected:?}",
+                connected.len()
+            ),
+            Self::BrokenLoop { at: (x, y) } => {
+                write!(f, "the loop is broken at x={x}, y={y}")
+            }
+        }
+    }
+}
+
+fn parse_grid(input: &str) -> (Vec<u8>, usize, usize) {
+    let width = input.lines().next().unwrap().len();
+    let height = input.lines().count();
+
+    let grid = input
+        .lines()
+        .flat_map(|line| line.as_bytes())
+        .copied()
+        .collect::<Vec<_>>();
+
+    (grid, width, height)
+}
+
+/// Offsets for left, right, top and bottom, in the same order as [`pipe_connections`].
+const DIRECTIONS: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+fn opposite(direction: usize) -> usize {
+    direction ^ 1
+}
+
+/// Returns the box-drawing character for a pipe.
+fn box_drawing_char(tile: u8) -> char {
+    match tile {
+        b'-' => '─',
+        b'|' => '│',
+        b'F' => '┌',
+        b'7' => '┐',
+        b'L' => '└',
+        b'J' => '┘',
+        _ => ' ',
     }
 }
 
+const PIPES: [(u8, [bool; 4]); 6] = [
+    (b'-', [true, true, false, false]),
+    (b'|', [false, false, true, true]),
+    (b'F', [false, true, false, true]),
+    (b'7', [true, false, false, true]),
+    (b'L', [false, true, true, false]),
+    (b'J', [true, false, true, false]),
+];
+
+/// Returns whether a pipe connects to the left, right, top and bottom.
+fn pipe_connections(tile: u8) -> [bool; 4] {
+    PIPES
+        .iter()
+        .find(|(pipe, _)| *pipe == tile)
+        .map_or([false; 4], |(_, connections)| *connections)
+}
+
+fn pipe_from_connections(connections: [bool; 4]) -> u8 {
+    PIPES.iter().find(|(_, c)| *c == connections).unwrap().0
+}
+
+#[test]
+fn test_render() {
+    let maze = PipeMaze::analyze(include_str!("input_sample_gold1.txt")).unwrap();
+    let expected = "\
+OOOOOOOOOOO
+O┌───────┐O
+O│┌─────┐│O
+O││OOOOO││O
+O││OOOOO││O
+O│└─┐O┌─┘│O
+O│II│O│II│O
+O└──┘O└──┘O
+OOOOOOOOOOO
+";
+    assert_eq!(expected, maze.render(false));
+
+    let colored = maze.render(true);
+    assert!(colored.starts_with("\x1b[2mOOOOOOOOOOO\x1b[0m\n\x1b[2mO\x1b[1;33m┌───────┐"));
+    assert!(colored.contains("\x1b[1;41mII\x1b[1;33m│"));
+}
+
+#[test]
+fn test_to_ppm() {
+    let maze = PipeMaze::analyze(include_str!("input_sample_gold1.txt")).unwrap();
+    let ppm = maze.to_ppm();
+
+    let header = b"P6\n33 27\n255\n";
+    assert!(ppm.starts_with(header));
+    assert_eq!(header.len() + 33 * 27 * 3, ppm.len());
+
+    let pixel = |x: usize, y: usize| {
+        let offset = header.len() + (x + y * 33) * 3;
+        [ppm[offset], ppm[offset + 1], ppm[offset + 2]]
+    };
+    assert_eq!([40, 40, 40], pixel(0, 0)); // outside
+    assert_eq!([255, 200, 0], pixel(4, 4)); // center of `S`
+    assert_eq!([255, 200, 0], pixel(5, 4)); // `S` is an `F` pipe
+    assert_eq!([0, 0, 0], pixel(3, 4));
+    assert_eq!([200, 40, 40], pixel(2 * 3 + 1, 6 * 3 + 1)); // enclosed
+}
+
+#[test]
+fn test_main_loop() {
+    let (grid, width, height) = parse_grid(Day::INPUT_SAMPLE);
+    let main_loop = MainLoop::find(&grid, width, height).unwrap();
+    assert_eq!(b'F', main_loop.start_tile);
+    assert_eq!(16, main_loop.vertices.len());
+    assert_eq!(&[(0, 2), (1, 2), (1, 1)], &main_loop.vertices[..3]);
+    assert_eq!(Some(&(0, 3)), main_loop.vertices.last());
+}
+
+#[test]
+fn test_main_loop_errors() {
+    let find = |input: &str| {
+        let (grid, width, height) = parse_grid(input);
+        MainLoop::find(&grid, width, height)
+    };
+
+    assert_eq!(Err(MazeError::NoStart), find("F7\nLJ"));
+    assert_eq!(Err(MazeError::MultipleStarts), find("S7\nLS"));
+    assert_eq!(
+        Err(MazeError::AmbiguousStart { connected: vec![] }),
+        find("...\n.S.\n...")
+    );
+    assert_eq!(
+        Err(MazeError::AmbiguousStart {
+            connected: vec![(0, 0)]
+        }),
+        find("|\nS")
+    );
+    assert_eq!(
+        Err(MazeError::AmbiguousStart {
+            connected: vec![(0, 1), (2, 1), (1, 2)]
+        }),
+        find("...\n-S-\n.|.")
+    );
+    assert_eq!(
+        Err(MazeError::BrokenLoop { at: (1, 1) }),
+        find("S7.\n|-.\nLJ.")
+    );
+    assert_eq!(
+        Err(MazeError::BrokenLoop { at: (2, 1) }),
+        find("S-7\n|.-\nL-J")
+    );
+    assert_eq!(
+        "the start tile must connect to exactly 2 pipes, but connects to 0: []",
+        find("S").unwrap_err().to_string()
+    );
+}
+
 #[test]
 fn test_silver_sample() {
     let output = Day::calculate_silver(Day::INPUT_SAMPLE);
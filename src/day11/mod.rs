@@ -8,109 +8,123 @@ impl SolutionSilver<usize> for Day {
     const INPUT_REAL: &'static str = include_str!("input_real.txt");
 
     fn calculate_silver(input: &str) -> usize {
-        let mut grid = input
-            .lines()
-            .map(|l| l.chars().collect::<Vec<_>>())
-            .collect::<Vec<_>>();
-
-        // duplicate empty columns
-        for x in (0..grid[0].len()).rev() {
-            if (0..grid.len()).all(|y| grid[y][x] == '.') {
-                for row in &mut grid {
-                    row.insert(x, '.');
-                }
-            }
-        }
-
-        // duplicate empty rows
-        for y in (0..grid.len()).rev() {
-            if grid[y].iter().all(|c| *c == '.') {
-                grid.insert(y, vec!['.'; grid[0].len()]);
-            }
-        }
-
-        debug_assert!(grid.iter().all(|r| r.len() == grid[0].len()));
-
-        let hashes = grid
-            .iter()
-            .enumerate()
-            .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, c)| ((x, y), c)))
-            .filter(|(_, c)| **c == '#')
-            .map(|(p, _)| p)
-            .collect::<Vec<_>>();
-
-        let mut sum = 0;
-        for i in 0..hashes.len() {
-            // manhattan distance between hashes
-            let a = hashes[i];
-            for b in hashes.iter().skip(i + 1) {
-                let dx = (a.0 as isize - b.0 as isize).abs();
-                let dy = (a.1 as isize - b.1 as isize).abs();
-                sum += dx + dy;
-            }
-        }
-
-        sum as usize
+        GalaxyMap::parse(input).sum_of_distances(2)
     }
 }
 
 impl SolutionGold<usize, usize> for Day {
     fn calculate_gold(input: &str) -> usize {
-        let mul_const = 1_000_000 - 1;
-        // let mul_const = 10 - 1;
+        GalaxyMap::parse(input).sum_of_distances(1_000_000)
+    }
+}
 
-        let grid = input
-            .lines()
-            .map(|l| l.chars().collect::<Vec<_>>())
-            .collect::<Vec<_>>();
+/// The galaxies in an image, along with the amount of empty rows and columns before each
+/// coordinate so the image can be expanded by any factor.
+pub struct GalaxyMap {
+    /// The `(x, y)` coordinates of every galaxy in the unexpanded image.
+    pub galaxies: Vec<(usize, usize)>,
+    empty_columns_before: Vec<usize>,
+    empty_rows_before: Vec<usize>,
+}
 
-        let mut hashes = grid
+impl GalaxyMap {
+    pub fn parse(input: &str) -> Self {
+        let grid = input.lines().map(str::as_bytes).collect::<Vec<_>>();
+        let width = grid[0].len();
+        let height = grid.len();
+
+        let galaxies = grid
             .iter()
             .enumerate()
             .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, c)| ((x, y), c)))
-            .filter(|(_, c)| **c == '#')
+            .filter(|(_, c)| **c == b'#')
             .map(|(p, _)| p)
             .collect::<Vec<_>>();
 
-        // duplicate empty columns
-        for x in (0..grid[0].len()).rev() {
-            let extend_count = (0..x)
-                .filter(|x| grid.iter().all(|row| row[*x] == '.'))
-                .count();
+        let mut column_has_galaxy = vec![false; width];
+        let mut row_has_galaxy = vec![false; height];
+        for (x, y) in &galaxies {
+            column_has_galaxy[*x] = true;
+            row_has_galaxy[*y] = true;
+        }
 
-            hashes
-                .iter_mut()
-                .filter(|(x_, _)| *x_ == x)
-                .for_each(|(x, _)| *x += mul_const * extend_count);
+        let prefix_sum = |has_galaxy: Vec<bool>| {
+            std::iter::once(0)
+                .chain(has_galaxy.iter().scan(0, |empty, has_galaxy| {
+                    *empty += usize::from(!has_galaxy);
+                    Some(*empty)
+                }))
+                .collect::<Vec<_>>()
+        };
+
+        Self {
+            galaxies,
+            empty_columns_before: prefix_sum(column_has_galaxy),
+            empty_rows_before: prefix_sum(row_has_galaxy),
         }
+    }
 
-        // duplicate empty rows
-        for y in (0..grid.len()).rev() {
-            let extend_count = (0..y)
-                .filter(|y| grid[*y].iter().all(|c| *c == '.'))
-                .count();
+    /// Returns the position of a galaxy after every empty row and column is replaced by `factor`
+    /// empty rows or columns. A factor of 0 removes the empty rows and columns.
+    pub fn expanded_position(&self, galaxy: usize, factor: usize) -> (usize, usize) {
+        let (x, y) = self.galaxies[galaxy];
+        let (empty_x, empty_y) = (self.empty_columns_before[x], self.empty_rows_before[y]);
+        (
+            x - empty_x + empty_x * factor,
+            y - empty_y + empty_y * factor,
+        )
+    }
 
-            hashes
-                .iter_mut()
-                .filter(|(_, y_)| *y_ == y)
-                .for_each(|(_, y)| *y += mul_const * extend_count);
-        }
+    /// Returns the manhattan distance between 2 galaxies in the expanded image.
+    pub fn distance(&self, a: usize, b: usize, factor: usize) -> usize {
+        let a = self.expanded_position(a, factor);
+        let b = self.expanded_position(b, factor);
+        a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+    }
 
-        let mut sum = 0;
-        for i in 0..hashes.len() {
-            // manhattan distance between hashes
-            let a = hashes[i];
-            for b in hashes.iter().skip(i + 1) {
-                let dx = (a.0 as isize - b.0 as isize).abs();
-                let dy = (a.1 as isize - b.1 as isize).abs();
-                sum += dx + dy;
+    /// Returns the sum of the distances between every pair of galaxies in the expanded image.
+    pub fn sum_of_distances(&self, factor: usize) -> usize {
+        let (mut xs, mut ys): (Vec<_>, Vec<_>) = (0..self.galaxies.len())
+            .map(|g| self.expanded_position(g, factor))
+            .unzip();
+        xs.sort_unstable();
+        ys.sort_unstable();
+
+        // once sorted, each coordinate is larger than all coordinates before it
+        let sum_of_differences = |sorted: &[usize]| {
+            let mut total = 0;
+            let mut prefix = 0;
+            for (i, v) in sorted.iter().enumerate() {
+                total += v * i - prefix;
+                prefix += v;
             }
-        }
+            total
+        };
 
-        sum as usize
+        sum_of_differences(&xs) + sum_of_differences(&ys)
     }
 }
 
+#[test]
+fn test_galaxy_map() {
+    let map = GalaxyMap::parse(Day::INPUT_SAMPLE);
+    assert_eq!(9, map.galaxies.len());
+    assert_eq!(1030, map.sum_of_distances(10));
+    assert_eq!(8410, map.sum_of_distances(100));
+    assert_eq!(292, map.sum_of_distances(1));
+
+    // galaxy numbers from the puzzle are 1-based
+    assert_eq!(9, map.distance(4, 8, 2));
+    assert_eq!(15, map.distance(0, 6, 2));
+    assert_eq!(17, map.distance(2, 5, 2));
+    assert_eq!(5, map.distance(7, 8, 2));
+    assert_eq!(map.distance(7, 8, 2), map.distance(8, 7, 2));
+
+    // without the empty rows and columns, the last two galaxies are 3 columns apart
+    assert_eq!(3, map.distance(7, 8, 0));
+    assert!(map.sum_of_distances(0) < map.sum_of_distances(1));
+}
+
 #[test]
 fn test_silver_sample() {
     let output = Day::calculate_silver(Day::INPUT_SAMPLE);
@@ -1,7 +1,7 @@
-use std::collections::HashMap;
-
 use super::*;
 
+pub mod nonogram;
+
 pub struct Day;
 
 impl SolutionSilver<usize> for Day {
@@ -16,14 +16,12 @@ impl SolutionSilver<usize> for Day {
                 let (line, arrangement) = l.split_once(' ').unwrap();
                 let line = line.as_bytes();
 
-                let mut arrangement = arrangement
+                let arrangement = arrangement
                     .split(',')
                     .map(|a| a.parse::<usize>().unwrap())
                     .collect::<Vec<_>>();
 
-                let mut line = line.to_vec();
-                let mut map = HashMap::new();
-                match_count_recursive(&mut line, &mut arrangement, &mut map)
+                count_arrangements(line, &arrangement)
             })
             .sum::<usize>()
     }
@@ -40,273 +38,296 @@ impl SolutionGold<usize, usize> for Day {
                 let mut line = once.repeat(5);
                 line.pop();
 
-                let mut arrangement = arrangement
+                let arrangement = arrangement
                     .split(',')
                     .map(|a| a.parse::<usize>().unwrap())
                     .collect::<Vec<_>>()
                     .repeat(5);
 
-                let mut map = HashMap::new();
-                match_count_recursive(&mut line, &mut arrangement, &mut map)
+                count_arrangements(&line, &arrangement)
             })
             .sum::<usize>()
     }
 }
 
-fn match_count_recursive(
-    line: &mut [u8],
-    arrangement: &mut [usize],
-    map: &mut HashMap<(Vec<u8>, Vec<usize>), usize>,
-) -> usize {
-    // don't keep matching the first bit over and over, cut off the first matches if possible
-    let Some((line, arrangement)) = match_no_wildcards_partial(line, arrangement) else {
-        return 0;
-    };
-
-    // trim starting dots
-    let pos = line.iter().position(|b| *b != b'.').unwrap_or_default();
-    let line = &mut line[pos..];
-
-    // trim early if there's no way we can match this
-    if line.len() < arrangement.len() + arrangement.iter().sum::<usize>().saturating_sub(1) {
-        return 0;
-    }
-
-    if let Some(&count) = map.get(&(line.to_vec(), arrangement.to_vec())) {
-        return count;
-    }
+/// Counts the ways the `?` in a line of `#`, `.` and `?` can be filled in so the groups of `#` match
+/// `arrangement`.
+pub fn count_arrangements(line: &[u8], arrangement: &[usize]) -> usize {
+    let count = ArrangementTable::new(line, arrangement).count();
+    usize::try_from(count).expect("too many arrangements to fit in a usize")
+}
 
-    if let Some(pos) = line.iter().position(|b| *b == b'?') {
-        if pos != 0 {
-            panic!(
-                "pos != 0, arr: {:?}, line: {:?}",
-                arrangement,
-                line.iter().map(|b| *b as char).collect::<String>()
-            );
-        }
+/// The memoized arrangement counts for every suffix of a line and of its groups. This can be used
+/// to count, list and sample arrangements without trying every combination.
+pub struct ArrangementTable<'a> {
+    line: &'a [u8],
+    groups: &'a [usize],
+    /// `counts[i * (groups.len() + 1) + j]` is the amount of ways `line[i..]` can match
+    /// `groups[j..]`.
+    counts: Vec<u128>,
+}
 
-        // only try `#` if we can fill out a group
-        let possible_group_size = line
-            .iter()
-            .take_while(|c| **c == b'#' || **c == b'?')
-            .count();
-        let can_take_immediately = !arrangement.is_empty()
-            && possible_group_size >= arrangement[0]
-            && line.len() >= arrangement[0] // TODO: useless check, this is done earlier
-            // && line.get(arrangement[0] + 1).cloned() != Some(b'#')
-            ;
-        let with_true = if can_take_immediately {
-            // let to_skip = (arrangement[0] + 1).min(line.len());
-            // match_count_recursive(&mut line[to_skip..], &mut arrangement[1..])
-
-            line[pos] = b'#';
-            match_count_recursive(line, arrangement, map)
-        } else {
-            0
+impl<'a> ArrangementTable<'a> {
+    pub fn new(line: &'a [u8], groups: &'a [usize]) -> Self {
+        let stride = groups.len() + 1;
+        let mut table = Self {
+            line,
+            groups,
+            counts: vec![0; (line.len() + 1) * stride],
         };
 
-        // line[pos] = b'.';
-        let with_false = match_count_recursive(&mut line[1..], arrangement, map);
-
-        line[pos] = b'?';
+        // an empty line only matches if there are no groups left
+        table.counts[line.len() * stride + groups.len()] = 1;
 
-        let sum = with_true + with_false;
+        for i in (0..line.len()).rev() {
+            for j in 0..=groups.len() {
+                let mut count = 0u128;
+                if line[i] != b'#' {
+                    count = table.get(i + 1, j);
+                }
+                if let Some(next) = table.place_group(i, j) {
+                    count = count
+                        .checked_add(table.get(next, j + 1))
+                        .expect("too many arrangements to fit in a u128");
+                }
+                table.counts[i * stride + j] = count;
+            }
+        }
 
-        map.insert((line.to_vec(), arrangement.to_vec()), sum);
+        table
+    }
 
-        sum
-    } else {
-        match_no_wildcards(line, arrangement) as usize
+    /// Returns the amount of ways `line[i..]` can match `groups[j..]`.
+    pub fn get(&self, i: usize, j: usize) -> u128 {
+        self.counts[i * (self.groups.len() + 1) + j]
     }
-}
 
-fn match_no_wildcards(line: &mut [u8], arrangement: &mut [usize]) -> bool {
-    if let Some((line_rest, arr_rest)) = match_no_wildcards_partial(line, arrangement) {
-        line_rest.is_empty() && arr_rest.is_empty()
-    } else {
-        false
+    /// Returns the total amount of arrangements.
+    pub fn count(&self) -> u128 {
+        self.get(0, 0)
     }
-}
 
-fn match_no_wildcards_partial<'a, 'b>(
-    line: &'a mut [u8],
-    arrangement: &'b mut [usize],
-) -> Option<(&'a mut [u8], &'b mut [usize])> {
-    let mut index = 0;
-    for (group_index, group_len) in arrangement.iter().enumerate() {
-        if index >= line.len() {
-            // end of line!
-            // group {group_index} not matched, past end of line
+    /// If group `j` can start at index `i`, returns the index after the group and its separator.
+    fn place_group(&self, i: usize, j: usize) -> Option<usize> {
+        let len = *self.groups.get(j)?;
+        let end = i + len;
+        if end > self.line.len()
+            || self.line[i..end].contains(&b'.')
+            || self.line.get(end) == Some(&b'#')
+        {
             return None;
         }
 
-        // skip empty bits
-        while line[index] == b'.' {
-            index += 1;
-
-            if index >= line.len() {
-                // group {group_index} not matched, didn't find start
-                return None;
-            }
-        }
+        Some((end + 1).min(self.line.len()))
+    }
 
-        if index != line.len() && line[index] == b'?' {
-            // group {group_index} start not matched, found wildcard
-            return Some((&mut line[index..], &mut arrangement[group_index..]));
+    /// Returns the `k`-th arrangement in lexicographic order, where `#` sorts before `.`.
+    pub fn nth(&self, mut k: u128) -> Option<Vec<u8>> {
+        if k >= self.count() {
+            return None;
         }
 
-        let group_start_index = index;
-
-        // skip non-empty bits
-        // only match starting on `#`, but accept non-first `?`
-        while line[index] == b'#'
-            || (index != group_start_index
-                && index - group_start_index != *group_len
-                && line[index] == b'?')
-        {
-            index += 1;
-
-            if index == line.len() {
-                if index - group_start_index != *group_len {
-                    // group {group_index} not matched, past end of line
-                    return None;
-                } else {
-                    break;
+        let mut arrangement = Vec::with_capacity(self.line.len());
+        let (mut i, mut j) = (0, 0);
+        while i < self.line.len() {
+            // starting a group puts a `#` here, so it comes first
+            if let Some(next) = self.place_group(i, j) {
+                let with_group = self.get(next, j + 1);
+                if k < with_group {
+                    arrangement.resize(i + self.groups[j], b'#');
+                    arrangement.resize(next, b'.');
+                    (i, j) = (next, j + 1);
+                    continue;
                 }
+                k -= with_group;
             }
-        }
 
-        // if we match a wildcard after matching a group, assume it is a `.`
-        if index != line.len() && line[index] == b'?' {
-            // group {group_index} not matched, found wildcard
-            index += 1;
-            continue;
+            debug_assert_ne!(b'#', self.line[i]);
+            arrangement.push(b'.');
+            i += 1;
         }
 
-        let matched_len = index - group_start_index;
-        if matched_len != *group_len {
-            // group {group_index} not matched, wrong length {matched_len}
-            return None;
-        }
+        Some(arrangement)
     }
 
-    if index == line.len() {
-        // matched line, no trailing `.`
-        return Some((&mut line[index..], &mut []));
+    /// Lists every arrangement in lexicographic order.
+    pub fn iter(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        (0..self.count()).map(|k| self.nth(k).unwrap())
     }
 
-    // skip empty bits
-    // TODO: ok?
-    while line[index] == b'.' {
-        index += 1;
-
-        if index >= line.len() {
-            break;
+    /// Picks an arrangement uniformly at random, using `random` as a source of random numbers.
+    pub fn sample(&self, random: &mut impl FnMut() -> u64) -> Option<Vec<u8>> {
+        let count = self.count();
+        if count == 0 {
+            return None;
         }
-    }
 
-    if index < line.len() {
-        // line not matched, not at end
-        if line[index] == b'?' {
-            return Some((&mut line[index..], &mut []));
-        }
-        return None;
-    }
+        // reject numbers from the incomplete final bucket to avoid modulo bias
+        let limit = u128::MAX - u128::MAX % count;
+        let k = loop {
+            let value = (random() as u128) << 64 | random() as u128;
+            if value < limit {
+                break value % count;
+            }
+        };
 
-    // matched line
-    Some((&mut line[index..], &mut []))
+        self.nth(k)
+    }
 }
 
 #[test]
 fn test_could_match_no_wildcard() {
-    assert!(match_no_wildcards(&mut b"#".to_vec(), &mut [1]));
-    assert!(match_no_wildcards(&mut b"##".to_vec(), &mut [2]));
-    assert!(match_no_wildcards(&mut b"#.".to_vec(), &mut [1]));
-    assert!(match_no_wildcards(&mut b".#".to_vec(), &mut [1]));
-    assert!(match_no_wildcards(&mut b".#.".to_vec(), &mut [1]));
-    assert!(match_no_wildcards(&mut b".".to_vec(), &mut []));
-    assert!(match_no_wildcards(&mut b"..".to_vec(), &mut []));
-
-    assert!(match_no_wildcards(&mut b".#.#.".to_vec(), &mut [1, 1]));
-    assert!(match_no_wildcards(&mut b".###.".to_vec(), &mut [3]));
-
-    assert!(!match_no_wildcards(&mut b".".to_vec(), &mut [1]));
-    assert!(!match_no_wildcards(&mut b"#".to_vec(), &mut []));
-
-    assert!(!match_no_wildcards(&mut b"##".to_vec(), &mut [3]));
-    assert!(!match_no_wildcards(&mut b"##".to_vec(), &mut [1, 1]));
+    assert_eq!(1, count_arrangements(b"#", &[1]));
+    assert_eq!(1, count_arrangements(b"##", &[2]));
+    assert_eq!(1, count_arrangements(b"#.", &[1]));
+    assert_eq!(1, count_arrangements(b".#", &[1]));
+    assert_eq!(1, count_arrangements(b".#.", &[1]));
+    assert_eq!(1, count_arrangements(b".", &[]));
+    assert_eq!(1, count_arrangements(b"..", &[]));
+
+    assert_eq!(1, count_arrangements(b".#.#.", &[1, 1]));
+    assert_eq!(1, count_arrangements(b".###.", &[3]));
+
+    assert_eq!(0, count_arrangements(b".", &[1]));
+    assert_eq!(0, count_arrangements(b"#", &[]));
+
+    assert_eq!(0, count_arrangements(b"##", &[3]));
+    assert_eq!(0, count_arrangements(b"##", &[1, 1]));
 }
 
 #[test]
 fn test_could_match() {
-    let mut map = HashMap::new();
-    assert_eq!(
-        match_count_recursive(&mut b"#?".to_vec(), &mut [2], &mut map),
-        1
-    );
-    assert_eq!(
-        match_count_recursive(&mut b"?#".to_vec(), &mut [2], &mut map),
-        1
-    );
-    assert_eq!(
-        match_count_recursive(&mut b"#?".to_vec(), &mut [1], &mut map),
-        1
-    );
-    assert_eq!(
-        match_count_recursive(&mut b"?#".to_vec(), &mut [1], &mut map),
-        1
-    );
+    assert_eq!(count_arrangements(b"#?", &[2]), 1);
+    assert_eq!(count_arrangements(b"?#", &[2]), 1);
+    assert_eq!(count_arrangements(b"#?", &[1]), 1);
+    assert_eq!(count_arrangements(b"?#", &[1]), 1);
 
-    assert_eq!(
-        match_count_recursive(&mut b"#?#".to_vec(), &mut [1, 1], &mut map),
-        1
-    );
-    assert_eq!(
-        match_count_recursive(&mut b"#?#".to_vec(), &mut [3], &mut map),
-        1
-    );
+    assert_eq!(count_arrangements(b"#?#", &[1, 1]), 1);
+    assert_eq!(count_arrangements(b"#?#", &[3]), 1);
 
-    assert_eq!(
-        match_count_recursive(&mut b"?###".to_vec(), &mut [3], &mut map),
-        1
-    );
+    assert_eq!(count_arrangements(b"?###", &[3]), 1);
 
-    assert_eq!(
-        match_count_recursive(&mut b"?#..".to_vec(), &mut [3], &mut map),
-        0
-    );
-    assert_eq!(
-        match_count_recursive(&mut b"..?#".to_vec(), &mut [3], &mut map),
-        0
-    );
+    assert_eq!(count_arrangements(b"?#..", &[3]), 0);
+    assert_eq!(count_arrangements(b"..?#", &[3]), 0);
 
-    assert_eq!(
-        match_count_recursive(&mut b"???".to_vec(), &mut [1], &mut map),
-        3
-    );
-    assert_eq!(
-        match_count_recursive(&mut b"?#?".to_vec(), &mut [2], &mut map),
-        2
-    );
+    assert_eq!(count_arrangements(b"???", &[1]), 3);
+    assert_eq!(count_arrangements(b"?#?", &[2]), 2);
 
     // samples from part 2
+    assert_eq!(count_arrangements(b".#?.#?.#?.#?.#", &[1, 1, 1, 1, 1]), 1);
+
     assert_eq!(
-        match_count_recursive(
-            &mut b".#?.#?.#?.#?.#".to_vec(),
-            &mut [1, 1, 1, 1, 1],
-            &mut map
+        count_arrangements(
+            b"???.###????.###????.###????.###????.###",
+            &[1, 1, 3, 1, 1, 3, 1, 1, 3, 1, 1, 3, 1, 1, 3]
         ),
         1
     );
+}
 
-    assert_eq!(
-        match_count_recursive(
-            &mut b"???.###????.###????.###????.###????.###".to_vec(),
-            &mut [1, 1, 3, 1, 1, 3, 1, 1, 3, 1, 1, 3, 1, 1, 3],
-            &mut map
-        ),
-        1
+#[test]
+fn test_count_arrangements_matches_brute_force() {
+    let brute_force = |line: &[u8], arrangement: &[usize]| {
+        let unknowns = line.iter().filter(|c| **c == b'?').count();
+        (0..1usize << unknowns)
+            .filter(|mask| {
+                let mut bit = 0;
+                let filled = line
+                    .iter()
+                    .map(|c| match *c {
+                        b'?' => {
+                            bit += 1;
+                            mask & (1 << (bit - 1)) != 0
+                        }
+                        c => c == b'#',
+                    })
+                    .collect::<Vec<_>>();
+                filled
+                    .split(|f| !f)
+                    .filter(|g| !g.is_empty())
+                    .map(<[bool]>::len)
+                    .eq(arrangement.iter().copied())
+            })
+            .count()
+    };
+
+    // every line of up to 6 cells against a few clues
+    let clues: [&[usize]; 6] = [&[], &[1], &[2], &[1, 1], &[2, 1], &[1, 1, 1]];
+    for len in 1..=6u32 {
+        for code in 0..3usize.pow(len) {
+            let line = (0..len)
+                .map(|i| [b'?', b'#', b'.'][code / 3usize.pow(i) % 3])
+                .collect::<Vec<_>>();
+            for clue in clues {
+                assert_eq!(
+                    brute_force(&line, clue),
+                    count_arrangements(&line, clue),
+                    "{:?} {clue:?}",
+                    String::from_utf8_lossy(&line)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_arrangement_table_iter() {
+    let table = ArrangementTable::new(b"?###????????", &[3, 2, 1]);
+    assert_eq!(10, table.count());
+
+    let arrangements = table.iter().collect::<Vec<_>>();
+    assert_eq!(10, arrangements.len());
+    assert!(arrangements.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(b".###.##.#...".to_vec(), arrangements[0]);
+    assert_eq!(b".###....##.#".to_vec(), arrangements[9]);
+    for arrangement in &arrangements {
+        assert_eq!(1, count_arrangements(arrangement, &[3, 2, 1]));
+    }
+
+    assert_eq!(Some(arrangements[4].clone()), table.nth(4));
+    assert_eq!(None, table.nth(10));
+
+    let table = ArrangementTable::new(b"#.#", &[2]);
+    assert_eq!(0, table.count());
+    assert_eq!(None, table.iter().next());
+}
+
+#[test]
+fn test_arrangement_table_large() {
+    // more arrangements than fit in a u64
+    let line = [b'?'; 120];
+    let groups = [1; 35];
+    let table = ArrangementTable::new(&line, &groups);
+    assert_eq!(1511553949020530244061128, table.count());
+
+    let last = table.nth(table.count() - 1).unwrap();
+    assert_eq!([b'.'; 51], last[..51]);
+    assert_eq!(b"#.#".as_ref(), &last[117..]);
+
+    let mut state = 0x1234_5678;
+    let sample = table.sample(&mut || crate::utils::xorshift64(&mut state));
+    assert_eq!(1, count_arrangements(&sample.unwrap(), &groups));
+}
+
+#[test]
+fn test_arrangement_table_sample() {
+    let table = ArrangementTable::new(b"?###????????", &[3, 2, 1]);
+    let arrangements = table.iter().collect::<Vec<_>>();
+
+    let mut state = 0x1234_5678;
+    let mut random = || crate::utils::xorshift64(&mut state);
+    let mut histogram = [0; 10];
+    for _ in 0..10_000 {
+        let sample = table.sample(&mut random).unwrap();
+        histogram[arrangements.iter().position(|a| *a == sample).unwrap()] += 1;
+    }
+    assert!(
+        histogram.iter().all(|h| (800..1200).contains(h)),
+        "{histogram:?}"
     );
+
+    let table = ArrangementTable::new(b"...", &[1]);
+    assert_eq!(None, table.sample(&mut random));
 }
 
 #[test]
@@ -0,0 +1,245 @@
+//! A solver for 2D nonograms, using the spring arrangement counter to find which cells of a line
+//! are forced.
+
+use super::count_arrangements;
+
+const UNKNOWN: u8 = b'?';
+const FILLED: u8 = b'#';
+const EMPTY: u8 = b'.';
+
+/// A puzzle where each row and column is described by the lengths of its groups of filled cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nonogram {
+    pub rows: Vec<Vec<usize>>,
+    pub columns: Vec<Vec<usize>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Line {
+    Row(usize),
+    Column(usize),
+}
+
+/// The outcome of [`Nonogram::solve`]. Grids are indexed as `grid[y][x]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Solutions {
+    Unique(Vec<Vec<bool>>),
+    Multiple(Vec<Vec<Vec<bool>>>),
+    None(Refutation),
+}
+
+/// A proof that a nonogram has no solution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Refutation {
+    /// After propagation, this line can't be filled in.
+    Contradiction(Line),
+    /// The cell at `(x, y)` can be neither filled nor empty.
+    Branch {
+        cell: (usize, usize),
+        filled: Box<Refutation>,
+        empty: Box<Refutation>,
+    },
+}
+
+impl Nonogram {
+    pub fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Finds all solutions by deducing forced cells line by line, and guessing a cell when that
+    /// no longer makes progress.
+    pub fn solve(&self) -> Solutions {
+        let grid = vec![UNKNOWN; self.width() * self.height()];
+
+        match self.solve_recursive(grid) {
+            Ok(mut solutions) if solutions.len() == 1 => Solutions::Unique(solutions.remove(0)),
+            Ok(solutions) => Solutions::Multiple(solutions),
+            Err(refutation) => Solutions::None(refutation),
+        }
+    }
+
+    fn solve_recursive(&self, mut grid: Vec<u8>) -> Result<Vec<Vec<Vec<bool>>>, Refutation> {
+        self.propagate(&mut grid)
+            .map_err(Refutation::Contradiction)?;
+
+        let Some(unknown) = grid.iter().position(|c| *c == UNKNOWN) else {
+            let solution = grid
+                .chunks(self.width().max(1))
+                .take(self.height())
+                .map(|row| row.iter().map(|c| *c == FILLED).collect())
+                .collect();
+            return Ok(vec![solution]);
+        };
+
+        let try_cell = |value| {
+            let mut grid = grid.clone();
+            grid[unknown] = value;
+            self.solve_recursive(grid)
+        };
+        match (try_cell(FILLED), try_cell(EMPTY)) {
+            (Err(filled), Err(empty)) => Err(Refutation::Branch {
+                cell: (unknown % self.width(), unknown / self.width()),
+                filled: Box::new(filled),
+                empty: Box::new(empty),
+            }),
+            (Ok(mut a), Ok(b)) => {
+                a.extend(b);
+                Ok(a)
+            }
+            (Ok(solutions), Err(_)) | (Err(_), Ok(solutions)) => Ok(solutions),
+        }
+    }
+
+    /// Fills in every cell that has the same value in all arrangements of its row or column,
+    /// until nothing changes. Returns the line that can't be filled in if there is one.
+    fn propagate(&self, grid: &mut [u8]) -> Result<(), Line> {
+        let mut dirty = (0..self.height())
+            .map(Line::Row)
+            .chain((0..self.width()).map(Line::Column))
+            .collect::<Vec<_>>();
+
+        while let Some(line) = dirty.pop() {
+            let (cells, clue) = match line {
+                Line::Row(y) => (
+                    (0..self.width())
+                        .map(|x| x + y * self.width())
+                        .collect::<Vec<_>>(),
+                    &self.rows[y],
+                ),
+                Line::Column(x) => (
+                    (0..self.height()).map(|y| x + y * self.width()).collect(),
+                    &self.columns[x],
+                ),
+            };
+
+            let mut values = cells.iter().map(|&i| grid[i]).collect::<Vec<_>>();
+            if count_arrangements(&values, clue) == 0 {
+                return Err(line);
+            }
+
+            for i in 0..values.len() {
+                if values[i] != UNKNOWN {
+                    continue;
+                }
+
+                values[i] = FILLED;
+                let can_be_filled = count_arrangements(&values, clue) != 0;
+                values[i] = EMPTY;
+                let can_be_empty = count_arrangements(&values, clue) != 0;
+
+                values[i] = match (can_be_filled, can_be_empty) {
+                    (true, true) => UNKNOWN,
+                    (true, false) => FILLED,
+                    (false, true) => EMPTY,
+                    (false, false) => return Err(line),
+                };
+
+                if values[i] != UNKNOWN {
+                    grid[cells[i]] = values[i];
+
+                    let crossing = match line {
+                        Line::Row(_) => Line::Column(i),
+                        Line::Column(_) => Line::Row(i),
+                    };
+                    if !dirty.contains(&crossing) {
+                        dirty.push(crossing);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn parse_grid(grid: &str) -> Vec<Vec<bool>> {
+    grid.lines()
+        .map(|l| l.bytes().map(|b| b == FILLED).collect())
+        .collect()
+}
+
+#[cfg(test)]
+fn clues_from_grid(grid: &[Vec<bool>]) -> Nonogram {
+    let groups = |line: Vec<bool>| {
+        line.split(|filled| !filled)
+            .filter(|group| !group.is_empty())
+            .map(<[bool]>::len)
+            .collect()
+    };
+
+    Nonogram {
+        rows: grid.iter().map(|row| groups(row.clone())).collect(),
+        columns: (0..grid[0].len())
+            .map(|x| groups(grid.iter().map(|row| row[x]).collect()))
+            .collect(),
+    }
+}
+
+#[test]
+fn test_solve_unique() {
+    let grid = parse_grid(
+        "\
+.###.
+#...#
+#####
+#...#
+#...#",
+    );
+    let nonogram = clues_from_grid(&grid);
+    assert_eq!(Solutions::Unique(grid), nonogram.solve());
+}
+
+#[test]
+fn test_solve_needs_backtracking() {
+    // a diagonal has 2 solutions that propagation can't tell apart
+    let nonogram = Nonogram {
+        rows: vec![vec![1], vec![1]],
+        columns: vec![vec![1], vec![1]],
+    };
+    assert_eq!(
+        Solutions::Multiple(vec![parse_grid("#.\n.#"), parse_grid(".#\n#."),]),
+        nonogram.solve()
+    );
+}
+
+#[test]
+fn test_solve_no_solution() {
+    let nonogram = Nonogram {
+        rows: vec![vec![2], vec![]],
+        columns: vec![vec![1], vec![]],
+    };
+    assert!(matches!(
+        nonogram.solve(),
+        Solutions::None(Refutation::Contradiction(_))
+    ));
+
+    // the middle row forces the middle column to be empty
+    let nonogram = Nonogram {
+        rows: vec![vec![1], vec![1, 1], vec![1]],
+        columns: vec![vec![1], vec![2], vec![1]],
+    };
+    assert!(matches!(
+        nonogram.solve(),
+        Solutions::None(Refutation::Contradiction(_))
+    ));
+
+    // 3 rows with 1 filled cell, but only 2 columns with 1 filled cell. propagation alone can't
+    // see this, so every guess has to be refuted.
+    let nonogram = Nonogram {
+        rows: vec![vec![1], vec![1], vec![1]],
+        columns: vec![vec![1], vec![1], vec![]],
+    };
+    let Solutions::None(refutation) = nonogram.solve() else {
+        panic!("expected no solution");
+    };
+    let Refutation::Branch { cell, filled, .. } = refutation else {
+        panic!("expected a branch");
+    };
+    assert_eq!((0, 0), cell);
+    assert!(matches!(*filled, Refutation::Contradiction(_)));
+}
@@ -10,52 +10,12 @@ impl SolutionSilver<usize> for Day {
     fn calculate_silver(input: &str) -> usize {
         input
             .split("\n\n")
-            .map(|grid| {
-                let grid = grid
-                    .lines()
-                    .map(|line| line.chars().collect::<Vec<_>>())
-                    .collect::<Vec<_>>();
-
-                let w = grid[0].len();
-                let h = grid.len();
-
-                let check_reflect_horizontal = |grid: &[Vec<_>], x: usize| {
-                    for c in 1..=(x.min(w - x)) {
-                        for y in 0..h {
-                            if grid[y][x - c] != grid[y][x + c - 1] {
-                                return false;
-                            }
-                        }
-                    }
-
-                    true
-                };
-
-                let check_reflect_vertical = |grid: &[Vec<_>], y: usize| {
-                    for c in 1..=(y.min(h - y)) {
-                        for x in 0..w {
-                            if grid[y - c][x] != grid[y + c - 1][x] {
-                                return false;
-                            }
-                        }
-                    }
-
-                    true
-                };
-
-                for x in 1..w {
-                    if check_reflect_horizontal(&grid, x) {
-                        return x;
-                    }
-                }
-
-                for y in 1..h {
-                    if check_reflect_vertical(&grid, y) {
-                        return y * 100;
-                    }
-                }
-
-                unreachable!("No solution found")
+            .map(|pattern| {
+                Pattern::parse(pattern)
+                    .reflections(0)
+                    .first()
+                    .expect("No solution found")
+                    .summary()
             })
             .sum()
     }
@@ -65,88 +25,242 @@ impl SolutionGold<usize, usize> for Day {
     fn calculate_gold(input: &str) -> usize {
         input
             .split("\n\n")
-            .map(|grid| {
-                let mut grid = grid
-                    .lines()
-                    .map(|line| line.chars().collect::<Vec<_>>())
-                    .collect::<Vec<_>>();
-
-                let w = grid[0].len();
-                let h = grid.len();
-
-                let check_reflect_horizontal = |grid: &[Vec<_>], x: usize| {
-                    for c in 1..=(x.min(w - x)) {
-                        for y in 0..h {
-                            if grid[y][x - c] != grid[y][x + c - 1] {
-                                return false;
-                            }
-                        }
-                    }
+            .map(|pattern| {
+                Pattern::parse(pattern)
+                    .reflections(1)
+                    .iter()
+                    .find(|r| r.mismatches.len() == 1)
+                    .expect("No solution found")
+                    .summary()
+            })
+            .sum()
+    }
+}
 
-                    true
-                };
+/// A pattern of ash and rocks, stored as bitmasks where a set bit is a rock. Each row and column
+/// is split over as many 64-bit words as it needs.
+pub struct Pattern {
+    pub width: usize,
+    pub height: usize,
+    /// Bit `x % 64` of `rows[y][x / 64]` is the cell at `(x, y)`.
+    pub rows: Vec<Vec<u64>>,
+    /// Bit `y % 64` of `columns[x][y / 64]` is the cell at `(x, y)`.
+    pub columns: Vec<Vec<u64>>,
+}
 
-                let check_reflect_vertical = |grid: &[Vec<_>], y: usize| {
-                    for c in 1..=(y.min(h - y)) {
-                        for x in 0..w {
-                            if grid[y - c][x] != grid[y + c - 1][x] {
-                                return false;
-                            }
-                        }
-                    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    /// A vertical line between 2 columns.
+    Vertical,
+    /// A horizontal line between 2 rows.
+    Horizontal,
+}
 
-                    true
-                };
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reflection {
+    pub axis: Axis,
+    /// The amount of columns or rows before the line.
+    pub position: usize,
+    /// The pairs of mirrored `(x, y)` cells that differ. Flipping either cell of each pair makes
+    /// the reflection perfect.
+    pub mismatches: Vec<((usize, usize), (usize, usize))>,
+}
 
-                let mut mirror_horizontal = None;
-                let mut mirror_vertical = None;
-                for x in 1..w {
-                    if check_reflect_horizontal(&grid, x) {
-                        mirror_horizontal = Some(x);
-                    }
-                }
+impl Pattern {
+    pub fn parse(pattern: &str) -> Self {
+        let lines = pattern.lines().map(str::as_bytes).collect::<Vec<_>>();
+        let width = lines[0].len();
+        let height = lines.len();
 
-                for y in 1..h {
-                    if check_reflect_vertical(&grid, y) {
-                        mirror_vertical = Some(y);
-                    }
+        let mut rows = vec![vec![0u64; width.div_ceil(64)]; height];
+        let mut columns = vec![vec![0u64; height.div_ceil(64)]; width];
+        for (y, line) in lines.iter().enumerate() {
+            for (x, _) in line.iter().enumerate().filter(|(_, c)| **c == b'#') {
+                rows[y][x / 64] |= 1 << (x % 64);
+                columns[x][y / 64] |= 1 << (y % 64);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            rows,
+            columns,
+        }
+    }
+
+    /// Returns every reflection line on both axes that needs at most `max_smudges` cells to be
+    /// changed. Vertical lines come first, both ordered by position.
+    pub fn reflections(&self, max_smudges: usize) -> Vec<Reflection> {
+        let vertical = (1..self.width).filter_map(|x| {
+            let mismatches = Self::reflect(&self.columns, x, max_smudges)?;
+            Some((Axis::Vertical, x, mismatches))
+        });
+        let horizontal = (1..self.height).filter_map(|y| {
+            let mismatches = Self::reflect(&self.rows, y, max_smudges)?;
+            Some((Axis::Horizontal, y, mismatches))
+        });
+
+        vertical
+            .chain(horizontal)
+            .map(|(axis, position, lines)| {
+                // `lines` contains (line, mirrored line, bit) tuples
+                let mismatches = lines
+                    .into_iter()
+                    .map(|(a, b, bit)| match axis {
+                        Axis::Vertical => ((a, bit), (b, bit)),
+                        Axis::Horizontal => ((bit, a), (bit, b)),
+                    })
+                    .collect();
+                Reflection {
+                    axis,
+                    position,
+                    mismatches,
                 }
+            })
+            .collect()
+    }
 
-                for mut_x in 0..w {
-                    for mut_y in 0..h {
-                        grid[mut_y][mut_x] = match grid[mut_y][mut_x] {
-                            '#' => '.',
-                            '.' => '#',
-                            _ => unreachable!(),
-                        };
-
-                        for x in 1..(w) {
-                            if mirror_horizontal != Some(x) && check_reflect_horizontal(&grid, x) {
-                                return x;
-                            }
-                        }
-
-                        for y in 1..(h) {
-                            if mirror_vertical != Some(y) && check_reflect_vertical(&grid, y) {
-                                return y * 100;
-                            }
-                        }
-
-                        // revert it again if no match was found
-                        grid[mut_y][mut_x] = match grid[mut_y][mut_x] {
-                            '#' => '.',
-                            '.' => '#',
-                            _ => unreachable!(),
-                        };
+    /// Checks a reflection between `lines[position - 1]` and `lines[position]`, where `lines` are
+    /// either the rows or the columns. Returns the differing bits if there are at most
+    /// `max_smudges` of them.
+    fn reflect(
+        lines: &[Vec<u64>],
+        position: usize,
+        max_smudges: usize,
+    ) -> Option<Vec<(usize, usize, usize)>> {
+        let mut mismatches = vec![];
+        for c in 1..=position.min(lines.len() - position) {
+            let (a, b) = (position - c, position + c - 1);
+            for (word, (x, y)) in lines[a].iter().zip(&lines[b]).enumerate() {
+                let mut diff = x ^ y;
+                while diff != 0 {
+                    if mismatches.len() == max_smudges {
+                        return None;
                     }
+                    let bit = word * 64 + diff.trailing_zeros() as usize;
+                    mismatches.push((a, b, bit));
+                    diff &= diff - 1;
                 }
+            }
+        }
 
-                unreachable!("No solution found")
-            })
-            .sum()
+        Some(mismatches)
+    }
+}
+
+impl Reflection {
+    /// The number used for the puzzle answer.
+    pub fn summary(&self) -> usize {
+        match self.axis {
+            Axis::Vertical => self.position,
+            Axis::Horizontal => self.position * 100,
+        }
     }
 }
 
+#[test]
+fn test_reflections() {
+    let mut patterns = Day::INPUT_SAMPLE.split("\n\n").map(Pattern::parse);
+    let first = patterns.next().unwrap();
+    let second = patterns.next().unwrap();
+
+    assert_eq!(
+        vec![Reflection {
+            axis: Axis::Vertical,
+            position: 5,
+            mismatches: vec![],
+        }],
+        first.reflections(0)
+    );
+    assert_eq!(
+        vec![
+            Reflection {
+                axis: Axis::Vertical,
+                position: 5,
+                mismatches: vec![],
+            },
+            Reflection {
+                axis: Axis::Horizontal,
+                position: 3,
+                mismatches: vec![((0, 0), (0, 5))],
+            },
+        ],
+        first.reflections(1)
+    );
+
+    assert_eq!(
+        vec![
+            Reflection {
+                axis: Axis::Horizontal,
+                position: 1,
+                mismatches: vec![((4, 0), (4, 1))],
+            },
+            Reflection {
+                axis: Axis::Horizontal,
+                position: 4,
+                mismatches: vec![],
+            },
+        ],
+        second.reflections(1)
+    );
+
+    // every line is a reflection if enough cells may change
+    let all = first.reflections(usize::MAX);
+    assert_eq!(first.width - 1 + first.height - 1, all.len());
+}
+
+#[test]
+fn test_reflections_large() {
+    // 80 columns, mirrored around the line after column 75
+    let mut state = 0x1234_5678;
+    let mut rows = (0..3)
+        .map(|_| {
+            let mut row = (0..80)
+                .map(|_| {
+                    if crate::utils::xorshift64(&mut state) & 1 == 1 {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<Vec<_>>();
+            for k in 0..5 {
+                row[75 + k] = row[74 - k];
+            }
+            row
+        })
+        .collect::<Vec<_>>();
+    let text = |rows: &[Vec<char>]| {
+        let lines = rows.iter().map(|r| r.iter().collect::<String>());
+        lines.collect::<Vec<_>>().join("\n")
+    };
+
+    let pattern = Pattern::parse(&text(&rows));
+    assert_eq!(2, pattern.rows[0].len());
+    assert!(pattern
+        .reflections(0)
+        .iter()
+        .any(|r| r.axis == Axis::Vertical && r.position == 75));
+
+    // a smudge past the first 64 columns
+    rows[1][77] = if rows[1][77] == '#' { '.' } else { '#' };
+    let pattern = Pattern::parse(&text(&rows));
+    let smudged = pattern.reflections(1);
+    let reflection = smudged.iter().find(|r| r.position == 75).unwrap();
+    assert_eq!(vec![((72, 1), (77, 1))], reflection.mismatches);
+
+    // the same pattern on its side, with 80 rows
+    let columns = (0..80)
+        .map(|x| rows.iter().map(|r| r[x]).collect())
+        .collect::<Vec<_>>();
+    let pattern = Pattern::parse(&text(&columns));
+    let smudged = pattern.reflections(1);
+    let reflection = smudged.iter().find(|r| r.position == 75).unwrap();
+    assert_eq!(Axis::Horizontal, reflection.axis);
+    assert_eq!(vec![((1, 72), (1, 77))], reflection.mismatches);
+}
+
 #[test]
 fn test_silver_sample() {
     let output = Day::calculate_silver(Day::INPUT_SAMPLE);
@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use super::*;
 
 pub struct Day;
@@ -10,165 +8,310 @@ impl SolutionSilver<usize> for Day {
     const INPUT_REAL: &'static str = include_str!("input_real.txt");
 
     fn calculate_silver(input: &str) -> usize {
-        let mut grid = input
-            .lines()
-            .map(|line| line.chars().collect::<Vec<_>>())
-            .collect::<Vec<_>>();
-
-        // move to the north
-
-        for _ in 0..grid.len() {
-            let mut changed = false;
-
-            for y in 1..grid.len() {
-                for x in 0..grid.len() {
-                    if grid[y][x] == 'O' && grid[y - 1][x] == '.' {
-                        grid[y][x] = '.';
-                        grid[y - 1][x] = 'O';
-                        changed = true;
-                    }
-                }
-            }
-
-            if !changed {
-                break;
-            }
-        }
-
-        let mut sum = 0;
-        for y in 0..grid.len() {
-            for x in 0..grid.len() {
-                if grid[y][x] == 'O' {
-                    sum += grid.len() - y;
-                }
-            }
-        }
-
-        sum
+        let mut platform = Platform::parse(input);
+        platform.tilt(Direction::North);
+        platform.load(Direction::North)
     }
 }
 
 impl SolutionGold<usize, usize> for Day {
     fn calculate_gold(input: &str) -> usize {
-        let mut grid = input
-            .lines()
-            .map(|line| line.as_bytes().to_vec())
-            .collect::<Vec<_>>();
-
-        let mut visited = HashMap::<Vec<Vec<u8>>, usize>::new();
-
-        let width = grid[0].len();
-        let height = grid.len();
-
-        const MAX_ITERATIONS: usize = 1000000000;
-        for current_iteration in 0..MAX_ITERATIONS {
-            // up
-            for _ in 1..height {
-                let mut changed = false;
-
-                for y in 1..width {
-                    for x in 0..height {
-                        if grid[y][x] == b'O' && grid[y - 1][x] == b'.' {
-                            grid[y][x] = b'.';
-                            grid[y - 1][x] = b'O';
-                            changed = true;
-                        }
-                    }
-                }
+        Platform::parse(input)
+            .spin_history()
+            .load_after(1_000_000_000, Direction::North)
+    }
+}
 
-                if !changed {
-                    break;
-                }
-            }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    West,
+    South,
+    East,
+}
 
-            // left
-            for _ in 1..width {
-                let mut changed = false;
-
-                for x in 1..height {
-                    for y in 0..width {
-                        if grid[y][x] == b'O' && grid[y][x - 1] == b'.' {
-                            grid[y][x] = b'.';
-                            grid[y][x - 1] = b'O';
-                            changed = true;
-                        }
-                    }
-                }
+/// Every state a platform goes through while spinning, up to the first repeated state.
+pub struct SpinHistory {
+    /// The amount of spin cycles before the platform enters a loop.
+    pub prefix: usize,
+    /// The amount of spin cycles in the loop.
+    pub period: usize,
+    /// The platform before spinning. The cube-shaped rocks never move, so each state only needs
+    /// the rounded rocks.
+    start: Platform,
+    /// `states[n]` is the `round` bitboard after `n` spin cycles.
+    states: Vec<Vec<u64>>,
+}
+
+/// A platform with rounded rocks (`O`) and cube-shaped rocks (`#`), stored as bitboards where bit
+/// `x + y * width` is the cell at `(x, y)`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Platform {
+    pub width: usize,
+    pub height: usize,
+    round: Vec<u64>,
+    cubes: Vec<u64>,
+}
 
-                if !changed {
-                    break;
+impl Platform {
+    pub fn parse(input: &str) -> Self {
+        let width = input.lines().next().unwrap().len();
+        let height = input.lines().count();
+
+        let words = (width * height).div_ceil(64);
+        let mut platform = Self {
+            width,
+            height,
+            round: vec![0; words],
+            cubes: vec![0; words],
+        };
+
+        for (y, line) in input.lines().enumerate() {
+            debug_assert_eq!(width, line.len());
+            for (x, c) in line.bytes().enumerate() {
+                match c {
+                    b'O' => set_bit(&mut platform.round, x + y * width),
+                    b'#' => set_bit(&mut platform.cubes, x + y * width),
+                    _ => (),
                 }
             }
+        }
 
-            // down
-            for _ in 1..height {
-                let mut changed = false;
-
-                for y in (0..(width - 1)).rev() {
-                    for x in 0..height {
-                        if grid[y][x] == b'O' && grid[y + 1][x] == b'.' {
-                            grid[y][x] = b'.';
-                            grid[y + 1][x] = b'O';
-                            changed = true;
-                        }
-                    }
-                }
+        platform
+    }
 
-                if !changed {
-                    break;
+    pub fn is_round_rock(&self, x: usize, y: usize) -> bool {
+        get_bit(&self.round, x + y * self.width)
+    }
+
+    pub fn is_cube_rock(&self, x: usize, y: usize) -> bool {
+        get_bit(&self.cubes, x + y * self.width)
+    }
+
+    /// Rolls all rounded rocks as far as they go in a direction. Each line is scanned once, moving
+    /// each rock directly to the first free slot after the last obstacle.
+    pub fn tilt(&mut self, direction: Direction) {
+        let (width, height) = (self.width, self.height);
+        let (line_count, line_len) = match direction {
+            Direction::North | Direction::South => (width, height),
+            Direction::West | Direction::East => (height, width),
+        };
+
+        // index of the `i`th cell in a line, counting from the side the rocks roll towards
+        let index_fn = |line: usize, i: usize| match direction {
+            Direction::North => line + i * width,
+            Direction::South => line + (height - 1 - i) * width,
+            Direction::West => i + line * width,
+            Direction::East => (width - 1 - i) + line * width,
+        };
+
+        for line in 0..line_count {
+            let mut free = 0;
+            for i in 0..line_len {
+                let index = index_fn(line, i);
+                if get_bit(&self.cubes, index) {
+                    free = i + 1;
+                } else if get_bit(&self.round, index) {
+                    clear_bit(&mut self.round, index);
+                    set_bit(&mut self.round, index_fn(line, free));
+                    free += 1;
                 }
             }
+        }
+    }
 
-            // right
-            for _ in 1..width {
-                let mut changed = false;
-
-                for x in (0..(height - 1)).rev() {
-                    for y in 0..width {
-                        if grid[y][x] == b'O' && grid[y][x + 1] == b'.' {
-                            grid[y][x] = b'.';
-                            grid[y][x + 1] = b'O';
-                            changed = true;
-                        }
-                    }
-                }
+    /// Tilts north, west, south and then east.
+    pub fn spin_cycle(&mut self) {
+        self.tilt(Direction::North);
+        self.tilt(Direction::West);
+        self.tilt(Direction::South);
+        self.tilt(Direction::East);
+    }
+
+    /// Returns a hash of the positions of the rounded rocks. The cube-shaped rocks never move, so
+    /// this identifies the state of the platform.
+    pub fn fingerprint(&self) -> u64 {
+        self.round.iter().fold(0, |hash, word| {
+            (hash.rotate_left(5) ^ word).wrapping_mul(0x517cc1b727220a95)
+        })
+    }
 
-                if !changed {
-                    break;
+    /// Keeps spinning the platform until it reaches a state it has been in before.
+    pub fn spin_history(&self) -> SpinHistory {
+        let mut states = vec![self.round.clone()];
+        let mut seen = ahash::AHashMap::from([(self.fingerprint(), 0)]);
+
+        let mut platform = self.clone();
+        loop {
+            platform.spin_cycle();
+            let fingerprint = platform.fingerprint();
+
+            // compare the full state in case of a hash collision
+            if let Some(&previous) = seen.get(&fingerprint) {
+                if states[previous] == platform.round {
+                    return SpinHistory {
+                        prefix: previous,
+                        period: states.len() - previous,
+                        start: self.clone(),
+                        states,
+                    };
                 }
             }
 
-            if let Some(last_iteration) = visited.get_mut(&grid) {
-                // we've found a cycle! we can now calculate which iteration contains the grid the
-                // final iteration would have had, so we don't need to go through the entire loop.
-                let cycle = current_iteration - *last_iteration;
-                let iterations_left = MAX_ITERATIONS - 1 - current_iteration;
-                let last_iteration_with_correct_grid = *last_iteration + iterations_left % cycle;
-
-                let (final_grid, _) = visited
-                    .into_iter()
-                    .find(|(_, v)| *v == last_iteration_with_correct_grid)
-                    .expect("find final cycle");
-                grid = final_grid;
-                break;
-            } else {
-                visited.insert(grid.clone(), current_iteration);
-            }
+            seen.insert(fingerprint, states.len());
+            states.push(platform.round.clone());
         }
+    }
 
+    /// Returns the total load on the support beams on the given side of the platform. Each rounded
+    /// rock adds the amount of rows or columns between it and the opposite edge, including its own.
+    pub fn load(&self, direction: Direction) -> usize {
         let mut sum = 0;
-        for y in 0..height {
-            for x in 0..width {
-                if grid[y][x] == b'O' {
-                    sum += grid.len() - y;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.is_round_rock(x, y) {
+                    sum += match direction {
+                        Direction::North => self.height - y,
+                        Direction::South => y + 1,
+                        Direction::West => self.width - x,
+                        Direction::East => x + 1,
+                    };
                 }
             }
         }
-
         sum
     }
 }
 
+impl SpinHistory {
+    /// Returns the platform after `n` spin cycles.
+    pub fn state_after(&self, n: usize) -> Platform {
+        let index = if n < self.states.len() {
+            n
+        } else {
+            self.prefix + (n - self.prefix) % self.period
+        };
+
+        Platform {
+            round: self.states[index].clone(),
+            ..self.start.clone()
+        }
+    }
+
+    /// Returns the load on the given side after `n` spin cycles.
+    pub fn load_after(&self, n: usize, direction: Direction) -> usize {
+        self.state_after(n).load(direction)
+    }
+}
+
+impl Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let c = if self.is_round_rock(x, y) {
+                    'O'
+                } else if self.is_cube_rock(x, y) {
+                    '#'
+                } else {
+                    '.'
+                };
+                write!(f, "{c}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn get_bit(bits: &[u64], index: usize) -> bool {
+    bits[index / 64] & (1 << (index % 64)) != 0
+}
+
+fn set_bit(bits: &mut [u64], index: usize) {
+    bits[index / 64] |= 1 << (index % 64);
+}
+
+fn clear_bit(bits: &mut [u64], index: usize) {
+    bits[index / 64] &= !(1 << (index % 64));
+}
+
+#[test]
+fn test_tilt() {
+    let mut platform = Platform::parse(Day::INPUT_SAMPLE);
+    platform.tilt(Direction::North);
+    let expected = "\
+OOOO.#.O..
+OO..#....#
+OO..O##..O
+O..#.OO...
+........#.
+..#....#.#
+..O..#.O.O
+..O.......
+#....###..
+#....#....
+";
+    assert_eq!(expected, platform.to_string());
+    assert_eq!(136, platform.load(Direction::North));
+}
+
+#[test]
+fn test_spin_cycle() {
+    let mut platform = Platform::parse(Day::INPUT_SAMPLE);
+    platform.spin_cycle();
+    let expected = "\
+.....#....
+....#...O#
+...OO##...
+.OO#......
+.....OOO#.
+.O#...O#.#
+....O#....
+......OOOO
+#...O###..
+#..OO#....
+";
+    assert_eq!(expected, platform.to_string());
+}
+
+#[test]
+fn test_spin_history() {
+    let history = Platform::parse(Day::INPUT_SAMPLE).spin_history();
+    assert_eq!(3, history.prefix);
+    assert_eq!(7, history.period);
+
+    let mut platform = Platform::parse(Day::INPUT_SAMPLE);
+    for n in 0..30 {
+        assert_eq!(platform, history.state_after(n), "n={n}");
+        assert_eq!(platform.fingerprint(), history.state_after(n).fingerprint());
+        platform.spin_cycle();
+    }
+
+    assert_eq!(64, history.load_after(1_000_000_000, Direction::North));
+    assert_eq!(
+        history.state_after(3),
+        history.state_after(3 + 7 * 1_000_000)
+    );
+}
+
+#[test]
+fn test_tilt_rectangular() {
+    let mut platform = Platform::parse("O.#.O\n.O..O\n..O#.\n");
+    platform.tilt(Direction::South);
+    assert_eq!("..#..\n....O\nOOO#O\n", platform.to_string());
+    platform.tilt(Direction::East);
+    assert_eq!("..#..\n....O\nOOO#O\n", platform.to_string());
+    assert_eq!(6, platform.load(Direction::North));
+    assert_eq!(5 + 1 + 2 + 3 + 5, platform.load(Direction::East));
+
+    platform.tilt(Direction::West);
+    platform.tilt(Direction::North);
+    assert_eq!("OO#.O\nO.O..\n...#.\n", platform.to_string());
+    assert_eq!(3 * 3 + 2 * 2, platform.load(Direction::North));
+    assert_eq!(5 + 4 + 1 + 5 + 3, platform.load(Direction::West));
+    assert_eq!(3 + 2 * 2, platform.load(Direction::South));
+}
+
 #[test]
 fn test_silver_sample() {
     let output = Day::calculate_silver(Day::INPUT_SAMPLE);
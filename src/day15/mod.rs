@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use crate::utils::fast_parse_int_from_bytes;
 
 use super::*;
@@ -12,74 +10,283 @@ impl SolutionSilver<usize> for Day {
     const INPUT_REAL: &'static str = include_str!("input_real.txt");
 
     fn calculate_silver(input: &str) -> usize {
-        input
-            .as_bytes()
-            .split(|b| *b == b',')
-            .map(|seq| {
-                let val = seq.iter().fold(0, |mut acc, c| {
-                    acc += *c as usize;
-                    acc *= 17;
-                    acc %= 256;
-                    acc
-                });
-
-                val
-            })
-            .sum()
+        input.as_bytes().split(|b| *b == b',').map(hash).sum()
     }
 }
 
 impl SolutionGold<usize, usize> for Day {
     fn calculate_gold(input: &str) -> usize {
-        let mut map = HashMap::<usize, Vec<(u32, usize)>>::new();
-        input.as_bytes().split(|b| *b == b',').for_each(|bytes| {
-            let label = &bytes[..bytes.iter().position(|b| *b == b'-' || *b == b'=').unwrap()];
-
-            let op = &bytes[label.len()..];
-
-            // labels are ascii 'a'-'z', which we can mask to 5 bits
-            // the longest label in the input is 6 chars, so that fits in 32 bits
-            let label_bits = label
-                .iter()
-                .enumerate()
-                .fold(0u32, |acc, (i, b)| acc + ((*b as u32 & 0b11111) << (i * 5)));
-
-            let label_hash = label.iter().fold(0, |mut acc, c| {
-                acc += *c as usize;
-                acc *= 17;
-                acc %= 256;
-                acc
-            });
-
-            if op[0] == b'=' {
-                let last = fast_parse_int_from_bytes(&op[1..]);
-                let vec = map.entry(label_hash).or_default();
-                if let Some(v) = vec.iter_mut().find(|i| i.0 == label_bits) {
-                    v.1 = last;
-                } else {
-                    vec.push((label_bits, last));
+        let mut boxes = LensBoxes::new();
+        for operation in parse_operations(input) {
+            boxes.apply(operation);
+        }
+        boxes.focusing_power()
+    }
+}
+
+/// The Holiday ASCII String Helper algorithm.
+pub fn hash(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |mut acc, c| {
+        acc += *c as usize;
+        acc *= 17;
+        acc %= 256;
+        acc
+    })
+}
+
+/// Parses the comma-separated initialization sequence.
+pub fn parse_operations(input: &str) -> impl Iterator<Item = Operation<'_>> {
+    input.trim_end().split(',').map(Operation::parse)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lens<'a> {
+    pub label: &'a str,
+    pub focal_length: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation<'a> {
+    /// `label=n`
+    Insert(Lens<'a>),
+    /// `label-`
+    Remove(&'a str),
+}
+
+/// What a single operation did to the boxes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change<'a> {
+    Added {
+        box_index: usize,
+        slot: usize,
+        lens: Lens<'a>,
+    },
+    Replaced {
+        box_index: usize,
+        slot: usize,
+        old: Lens<'a>,
+        new: Lens<'a>,
+    },
+    Removed {
+        box_index: usize,
+        slot: usize,
+        lens: Lens<'a>,
+    },
+    /// A lens was removed from a box that didn't contain it.
+    Unchanged { box_index: usize },
+}
+
+/// The 256 boxes of the HASHMAP procedure, which can be updated one operation at a time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LensBoxes<'a> {
+    boxes: Vec<Vec<Lens<'a>>>,
+}
+
+impl<'a> Operation<'a> {
+    pub fn parse(operation: &'a str) -> Self {
+        if let Some(label) = operation.strip_suffix('-') {
+            Self::Remove(label)
+        } else if let Some((label, focal_length)) = operation.split_once('=') {
+            Self::Insert(Lens {
+                label,
+                focal_length: fast_parse_int_from_bytes(focal_length.as_bytes()),
+            })
+        } else {
+            panic!("Invalid operation {operation:?}, expected `label-` or `label=length`");
+        }
+    }
+
+    pub fn label(&self) -> &'a str {
+        match self {
+            Self::Insert(lens) => lens.label,
+            Self::Remove(label) => label,
+        }
+    }
+
+    /// The index of the box this operation applies to.
+    pub fn box_index(&self) -> usize {
+        hash(self.label().as_bytes())
+    }
+}
+
+impl<'a> LensBoxes<'a> {
+    pub fn new() -> Self {
+        Self {
+            boxes: vec![vec![]; 256],
+        }
+    }
+
+    /// Applies a single operation and returns what it changed.
+    pub fn apply(&mut self, operation: Operation<'a>) -> Change<'a> {
+        let box_index = operation.box_index();
+        let lenses = &mut self.boxes[box_index];
+        let slot = lenses.iter().position(|l| l.label == operation.label());
+
+        match (operation, slot) {
+            (Operation::Insert(new), Some(slot)) => {
+                let old = std::mem::replace(&mut lenses[slot], new);
+                Change::Replaced {
+                    box_index,
+                    slot,
+                    old,
+                    new,
                 }
-            } else if op[0] == b'-' {
-                let vec = map.entry(label_hash).or_default();
-                if let Some(v) = vec.iter_mut().position(|i| i.0 == label_bits) {
-                    vec.remove(v);
+            }
+            (Operation::Insert(lens), None) => {
+                lenses.push(lens);
+                Change::Added {
+                    box_index,
+                    slot: lenses.len() - 1,
+                    lens,
                 }
-            } else {
-                unreachable!("invalid op: {:?}", op);
             }
-        });
+            (Operation::Remove(_), Some(slot)) => Change::Removed {
+                box_index,
+                slot,
+                lens: lenses.remove(slot),
+            },
+            (Operation::Remove(_), None) => Change::Unchanged { box_index },
+        }
+    }
+
+    /// Returns the lenses in a box, from front to back.
+    pub fn get(&self, box_index: usize) -> &[Lens<'a>] {
+        &self.boxes[box_index]
+    }
 
-        map.into_iter()
-            .map(|(box_idx, v)| {
-                v.iter()
+    pub fn focusing_power(&self) -> usize {
+        self.boxes
+            .iter()
+            .enumerate()
+            .map(|(box_index, lenses)| {
+                lenses
+                    .iter()
                     .enumerate()
-                    .map(|(pos, focal_len)| (box_idx + 1) * (pos + 1) * (focal_len.1))
+                    .map(|(slot, lens)| (box_index + 1) * (slot + 1) * lens.focal_length)
                     .sum::<usize>()
             })
             .sum()
     }
 }
 
+impl Default for LensBoxes<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for Lens<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{} {}]", self.label, self.focal_length)
+    }
+}
+
+/// Prints the non-empty boxes like the puzzle statement does.
+impl Display for LensBoxes<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (box_index, lenses) in self.boxes.iter().enumerate() {
+            if lenses.is_empty() {
+                continue;
+            }
+            write!(f, "Box {box_index}:")?;
+            for lens in lenses {
+                write!(f, " {lens}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Change<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Added {
+                box_index,
+                slot,
+                lens,
+            } => write!(f, "Box {box_index}: added {lens} in slot {slot}"),
+            Self::Replaced {
+                box_index,
+                slot,
+                old,
+                new,
+            } => write!(
+                f,
+                "Box {box_index}: replaced {old} with {new} in slot {slot}"
+            ),
+            Self::Removed {
+                box_index,
+                slot,
+                lens,
+            } => write!(f, "Box {box_index}: removed {lens} from slot {slot}"),
+            Self::Unchanged { box_index } => write!(f, "Box {box_index}: unchanged"),
+        }
+    }
+}
+
+#[test]
+fn test_lens_boxes() {
+    let mut boxes = LensBoxes::new();
+    let mut operations = parse_operations(Day::INPUT_SAMPLE);
+
+    let mut step = |boxes: &mut LensBoxes<'static>| boxes.apply(operations.next().unwrap());
+    assert_eq!(
+        "Box 0: added [rn 1] in slot 0",
+        step(&mut boxes).to_string()
+    );
+    assert_eq!("Box 0: unchanged", step(&mut boxes).to_string());
+    assert_eq!(
+        "Box 1: added [qp 3] in slot 0",
+        step(&mut boxes).to_string()
+    );
+    assert_eq!(
+        "Box 0: added [cm 2] in slot 1",
+        step(&mut boxes).to_string()
+    );
+    assert_eq!("Box 0: [rn 1] [cm 2]\nBox 1: [qp 3]\n", boxes.to_string());
+    assert_eq!(
+        "Box 1: removed [qp 3] from slot 0",
+        step(&mut boxes).to_string()
+    );
+    assert!(boxes.get(1).is_empty());
+
+    for _ in 0..4 {
+        step(&mut boxes);
+    }
+    assert_eq!(
+        "Box 3: added [pc 6] in slot 2",
+        step(&mut boxes).to_string()
+    );
+    assert_eq!(
+        Change::Replaced {
+            box_index: 3,
+            slot: 0,
+            old: Lens {
+                label: "ot",
+                focal_length: 9
+            },
+            new: Lens {
+                label: "ot",
+                focal_length: 7
+            },
+        },
+        step(&mut boxes)
+    );
+
+    assert_eq!(
+        "Box 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]\n",
+        boxes.to_string()
+    );
+    assert_eq!(145, boxes.focusing_power());
+}
+
+#[test]
+#[should_panic(expected = "Invalid operation \"rn\"")]
+fn test_invalid_operation() {
+    Operation::parse("rn");
+}
+
 #[test]
 fn test_silver_sample() {
     let output = Day::calculate_silver(Day::INPUT_SAMPLE);
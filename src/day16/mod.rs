@@ -1,7 +1,5 @@
 use std::collections::VecDeque;
 
-use ahash::AHashMap;
-
 use super::*;
 
 pub struct Day;
@@ -12,147 +10,551 @@ impl SolutionSilver<usize> for Day {
     const INPUT_REAL: &'static str = include_str!("input_real.txt");
 
     fn calculate_silver(input: &str) -> usize {
-        let grid = input.as_bytes().split(|b| *b == b'\n').collect::<Vec<_>>();
+        let contraption = Contraption::parse(input);
+        contraption
+            .trace((0, 0), Direction::Right)
+            .iter()
+            .filter(|dirs| **dirs != 0)
+            .count()
+    }
+}
 
-        let mut queue = VecDeque::<((isize, isize), (isize, isize))>::new();
-        queue.push_back(((0, 0), (1, 0)));
+impl SolutionGold<usize, usize> for Day {
+    fn calculate_gold(input: &str) -> usize {
+        Contraption::parse(input).best_entry().energized
+    }
+}
 
-        // visited is a hasmap instead of a hashset so we can more easily track visited
-        // positions later
-        let mut visited = AHashMap::<(isize, isize), usize>::new();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Down,
+    Up,
+    Left,
+    Right,
+}
 
-        while let Some((pos, dir)) = queue.pop_front() {
-            if pos.0 < 0
-                || pos.1 < 0
-                || pos.0 >= grid[0].len() as isize
-                || pos.1 >= grid.len() as isize
-            {
-                continue;
-            }
+/// A place where a beam enters the contraption.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub position: (usize, usize),
+    pub direction: Direction,
+    /// The amount of tiles energized by a beam entering here.
+    pub energized: usize,
+}
 
-            let dir_bitmap = match dir {
-                (0, 1) => 1 << 0,
-                (0, -1) => 1 << 1,
-                (-1, 0) => 1 << 2,
-                (1, 0) => 1 << 3,
-                _ => unreachable!("unknown dir: {:?}", dir),
-            };
-            let entry = visited.entry(pos).or_default();
-            if *entry & dir_bitmap != 0 {
+pub struct Contraption {
+    pub width: usize,
+    pub height: usize,
+    pub grid: Vec<u8>,
+}
+
+/// A beam travelling in a straight line from a start tile up to and including the first tile that
+/// changes its direction, or the edge of the grid.
+struct Segment {
+    tiles: Vec<usize>,
+    successors: Vec<usize>,
+}
+
+impl Direction {
+    fn offset(self) -> (isize, isize) {
+        match self {
+            Self::Down => (0, 1),
+            Self::Up => (0, -1),
+            Self::Left => (-1, 0),
+            Self::Right => (1, 0),
+        }
+    }
+
+    /// The bit used for this direction in the bitmaps returned by [`Contraption::trace`].
+    pub fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl Contraption {
+    pub fn parse(input: &str) -> Self {
+        let width = input.lines().next().unwrap().len();
+        let height = input.lines().count();
+        let grid = input.lines().flat_map(str::bytes).collect();
+
+        Self {
+            width,
+            height,
+            grid,
+        }
+    }
+
+    /// Returns the tile next to `index` in the given direction, if it is inside the grid.
+    fn step(&self, index: usize, direction: Direction) -> Option<usize> {
+        let (dx, dy) = direction.offset();
+        let x = (index % self.width).checked_add_signed(dx)?;
+        let y = (index / self.width).checked_add_signed(dy)?;
+        (x < self.width && y < self.height).then_some(x + y * self.width)
+    }
+
+    /// Returns the directions a beam leaves a tile in.
+    fn outgoing(tile: u8, direction: Direction) -> &'static [Direction] {
+        use Direction::*;
+        match (tile, direction) {
+            (b'.', Down) | (b'|', Down) => &[Down],
+            (b'.', Up) | (b'|', Up) => &[Up],
+            (b'.', Left) | (b'-', Left) => &[Left],
+            (b'.', Right) | (b'-', Right) => &[Right],
+            (b'/', Down) | (b'\\', Up) => &[Left],
+            (b'/', Up) | (b'\\', Down) => &[Right],
+            (b'/', Right) | (b'\\', Left) => &[Up],
+            (b'/', Left) | (b'\\', Right) => &[Down],
+            (b'-', Up | Down) => &[Left, Right],
+            (b'|', Left | Right) => &[Up, Down],
+            (c, _) => unreachable!("unknown char: {}", c as char),
+        }
+    }
+
+    /// Follows a beam and returns, for each tile, a bitmap of the directions beams crossed it in.
+    pub fn trace(&self, position: (usize, usize), direction: Direction) -> Vec<u8> {
+        let mut visited = vec![0u8; self.grid.len()];
+
+        let mut queue = VecDeque::new();
+        queue.push_back((position.0 + position.1 * self.width, direction));
+
+        while let Some((index, direction)) = queue.pop_front() {
+            if visited[index] & direction.bit() != 0 {
                 // already visited
                 continue;
             }
-            *entry |= dir_bitmap;
+            visited[index] |= direction.bit();
 
-            match (grid[pos.1 as usize][pos.0 as usize], dir) {
-                (b'.', _) | (b'-', (_, 0)) | (b'|', (0, _)) => {
-                    queue.push_back(((pos.0 + dir.0, pos.1 + dir.1), dir));
+            for &next_direction in Self::outgoing(self.grid[index], direction) {
+                if let Some(next) = self.step(index, next_direction) {
+                    queue.push_back((next, next_direction));
                 }
-                (b'/', (0, 1)) | (b'\\', (0, -1)) => {
-                    queue.push_back(((pos.0 - 1, pos.1), (-1, 0)));
+            }
+        }
+
+        visited
+    }
+
+    /// Follows a beam one step at a time. Each layer holds the tiles and directions that were
+    /// reached for the first time in that step, so the first layer is just the starting tile.
+    pub fn trace_layers(
+        &self,
+        position: (usize, usize),
+        direction: Direction,
+    ) -> Vec<Vec<((usize, usize), Direction)>> {
+        let mut visited = vec![0u8; self.grid.len()];
+        let mut layers = vec![];
+
+        let start = position.0 + position.1 * self.width;
+        visited[start] |= direction.bit();
+        let mut front = vec![(start, direction)];
+
+        while !front.is_empty() {
+            let mut next_front = vec![];
+            for &(index, direction) in &front {
+                for &next_direction in Self::outgoing(self.grid[index], direction) {
+                    if let Some(next) = self.step(index, next_direction) {
+                        if visited[next] & next_direction.bit() == 0 {
+                            visited[next] |= next_direction.bit();
+                            next_front.push((next, next_direction));
+                        }
+                    }
                 }
-                (b'/', (0, -1)) | (b'\\', (0, 1)) => {
-                    queue.push_back(((pos.0 + 1, pos.1), (1, 0)));
+            }
+
+            let position = |index| (index % self.width, index / self.width);
+            layers.push(front.iter().map(|&(i, d)| (position(i), d)).collect());
+            front = next_front;
+        }
+
+        layers
+    }
+
+    /// Draws the contraption with every tile coloured by how many directions beams crossed it in,
+    /// using a bitmap returned by [`Self::trace`]. Without colours, the empty tiles that were
+    /// crossed show that amount instead.
+    pub fn render_heatmap(&self, visited: &[u8], colored: bool) -> String {
+        self.render_frame(visited, &[], colored)
+    }
+
+    /// Draws every step of a beam trace, with the front of the beam shown as arrows on top of the
+    /// heatmap of the tiles energized so far.
+    pub fn animation_frames(
+        &self,
+        position: (usize, usize),
+        direction: Direction,
+        colored: bool,
+    ) -> Vec<String> {
+        let mut visited = vec![0u8; self.grid.len()];
+        self.trace_layers(position, direction)
+            .into_iter()
+            .map(|layer| {
+                let front = layer
+                    .into_iter()
+                    .map(|((x, y), direction)| {
+                        visited[x + y * self.width] |= direction.bit();
+                        (x + y * self.width, direction)
+                    })
+                    .collect::<Vec<_>>();
+                self.render_frame(&visited, &front, colored)
+            })
+            .collect()
+    }
+
+    fn render_frame(&self, visited: &[u8], front: &[(usize, Direction)], colored: bool) -> String {
+        const HEAT_COLORS: [&str; 5] = [
+            "\x1b[2m",
+            "\x1b[30;44m",
+            "\x1b[30;42m",
+            "\x1b[30;43m",
+            "\x1b[30;41m",
+        ];
+        const FRONT_COLOR: &str = "\x1b[1;97;45m";
+        const RESET: &str = "\x1b[0m";
+
+        let mut output = String::with_capacity(self.grid.len() * 2);
+        for y in 0..self.height {
+            let mut previous_color = None;
+            for x in 0..self.width {
+                let index = x + y * self.width;
+                let heat = visited[index].count_ones() as usize;
+                let arrow = front
+                    .iter()
+                    .find(|(i, _)| *i == index)
+                    .map(|(_, d)| match d {
+                        Direction::Down => 'v',
+                        Direction::Up => '^',
+                        Direction::Left => '<',
+                        Direction::Right => '>',
+                    });
+
+                if colored {
+                    let color = if arrow.is_some() {
+                        FRONT_COLOR
+                    } else {
+                        HEAT_COLORS[heat]
+                    };
+                    if previous_color != Some(color) {
+                        output.push_str(color);
+                        previous_color = Some(color);
+                    }
                 }
-                (b'/', (1, 0)) | (b'\\', (-1, 0)) => {
-                    queue.push_back(((pos.0, pos.1 - 1), (0, -1)));
+
+                output.push(match (arrow, self.grid[index]) {
+                    (Some(arrow), _) => arrow,
+                    (None, b'.') if !colored && heat > 0 => (b'0' + heat as u8) as char,
+                    (None, c) => c as char,
+                });
+            }
+            if colored {
+                output.push_str(RESET);
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+
+    /// Exports a heatmap as a binary PPM image, using a bitmap returned by [`Self::trace`]. Each
+    /// tile is drawn as a 3x3 block of pixels, with mirrors and splitters drawn over the colour of
+    /// the tile.
+    pub fn heatmap_ppm(&self, visited: &[u8]) -> Vec<u8> {
+        const SCALE: usize = 3;
+        const HEAT_COLORS: [[u8; 3]; 5] = [
+            [20, 20, 20],
+            [40, 60, 160],
+            [40, 150, 60],
+            [220, 180, 0],
+            [200, 40, 40],
+        ];
+        const MIRROR_COLOR: [u8; 3] = [255, 255, 255];
+
+        let image_width = self.width * SCALE;
+        let image_height = self.height * SCALE;
+        let mut pixels = vec![[0u8; 3]; image_width * image_height];
+
+        for index in 0..self.grid.len() {
+            let x = index % self.width;
+            let y = index / self.width;
+
+            // which of the 3x3 pixels are part of the mirror or splitter
+            let shape: [u8; 9] = match self.grid[index] {
+                b'/' => [0, 0, 1, 0, 1, 0, 1, 0, 0],
+                b'\\' => [1, 0, 0, 0, 1, 0, 0, 0, 1],
+                b'|' => [0, 1, 0, 0, 1, 0, 0, 1, 0],
+                b'-' => [0, 0, 0, 1, 1, 1, 0, 0, 0],
+                _ => [0; 9],
+            };
+
+            for (i, is_mirror) in shape.iter().enumerate() {
+                let color = if *is_mirror == 1 {
+                    MIRROR_COLOR
+                } else {
+                    HEAT_COLORS[visited[index].count_ones() as usize]
+                };
+                let pixel_x = x * SCALE + i % SCALE;
+                let pixel_y = y * SCALE + i / SCALE;
+                pixels[pixel_x + pixel_y * image_width] = color;
+            }
+        }
+
+        let mut output = format!("P6\n{image_width} {image_height}\n255\n").into_bytes();
+        output.extend(pixels.iter().flatten());
+        output
+    }
+
+    /// Returns every tile on the edge of the grid along with the direction pointing inwards.
+    pub fn edge_entries(&self) -> impl Iterator<Item = ((usize, usize), Direction)> + '_ {
+        let (w, h) = (self.width, self.height);
+        (0..h)
+            .map(|y| ((0, y), Direction::Right))
+            .chain((0..h).map(move |y| ((w - 1, y), Direction::Left)))
+            .chain((0..w).map(|x| ((x, 0), Direction::Down)))
+            .chain((0..w).map(move |x| ((x, h - 1), Direction::Up)))
+    }
+
+    /// Finds the edge entry that energizes the most tiles.
+    ///
+    /// Rather than tracing a beam for each entry, this splits all beams into straight segments and
+    /// groups the segments that can reach each other into strongly connected components. Each
+    /// component energizes the same tiles, so they only need to be calculated once.
+    pub fn best_entry(&self) -> Entry {
+        let (segments, entry_segments) = self.build_segments();
+        let components = strongly_connected_components(&segments);
+
+        // components are in reverse topological order, so successors are always done first
+        let words = self.grid.len().div_ceil(64);
+        let mut component_of = vec![0; segments.len()];
+        let mut energized = Vec::<Vec<u64>>::with_capacity(components.len());
+        for (component, members) in components.iter().enumerate() {
+            for &segment in members {
+                component_of[segment] = component;
+            }
+
+            let mut tiles = vec![0u64; words];
+            for &segment in members {
+                for &tile in &segments[segment].tiles {
+                    tiles[tile / 64] |= 1 << (tile % 64);
                 }
-                (b'/', (-1, 0)) | (b'\\', (1, 0)) => {
-                    queue.push_back(((pos.0, pos.1 + 1), (0, 1)));
+                for &successor in &segments[segment].successors {
+                    let successor = component_of[successor];
+                    if successor != component {
+                        for (word, other) in tiles.iter_mut().zip(&energized[successor]) {
+                            *word |= other;
+                        }
+                    }
                 }
-                (b'-', (0, _)) => {
-                    queue.push_back(((pos.0 + 1, pos.1), (1, 0)));
-                    queue.push_back(((pos.0 - 1, pos.1), (-1, 0)));
+            }
+            energized.push(tiles);
+        }
+
+        self.edge_entries()
+            .zip(entry_segments)
+            .map(|((position, direction), segment)| Entry {
+                position,
+                direction,
+                energized: energized[component_of[segment]]
+                    .iter()
+                    .map(|w| w.count_ones() as usize)
+                    .sum(),
+            })
+            .reduce(|best, entry| {
+                if entry.energized > best.energized {
+                    entry
+                } else {
+                    best
                 }
-                (b'|', (_, 0)) => {
-                    queue.push_back(((pos.0, pos.1 + 1), (0, 1)));
-                    queue.push_back(((pos.0, pos.1 - 1), (0, -1)));
+            })
+            .unwrap()
+    }
+
+    /// Builds every segment reachable from the edges. Returns the segments and the segment each
+    /// edge entry starts, in the order of [`Self::edge_entries`].
+    fn build_segments(&self) -> (Vec<Segment>, Vec<usize>) {
+        let mut segments = Vec::<Segment>::new();
+        let mut segment_ids = vec![usize::MAX; self.grid.len() * 4];
+
+        // returns the segment starting at a tile, adding it to the queue if it's new
+        let mut get_or_add = |segments: &mut Vec<Segment>,
+                              queue: &mut Vec<_>,
+                              index: usize,
+                              direction: Direction| {
+            let slot = &mut segment_ids[index * 4 + direction as usize];
+            if *slot == usize::MAX {
+                *slot = segments.len();
+                segments.push(Segment {
+                    tiles: vec![],
+                    successors: vec![],
+                });
+                queue.push((*slot, index, direction));
+            }
+            *slot
+        };
+
+        let mut queue = vec![];
+        let entry_segments = self
+            .edge_entries()
+            .map(|((x, y), direction)| {
+                get_or_add(&mut segments, &mut queue, x + y * self.width, direction)
+            })
+            .collect::<Vec<_>>();
+
+        while let Some((segment, start, start_direction)) = queue.pop() {
+            let mut tiles = vec![];
+            let mut index = start;
+            let mut successors = vec![];
+            loop {
+                tiles.push(index);
+                let outgoing = Self::outgoing(self.grid[index], start_direction);
+                if outgoing == [start_direction] {
+                    match self.step(index, start_direction) {
+                        Some(next) => index = next,
+                        None => break,
+                    }
+                    continue;
                 }
-                (c, _) => {
-                    unreachable!("unknown char: {c}");
+
+                for &direction in outgoing {
+                    if let Some(next) = self.step(index, direction) {
+                        successors.push(get_or_add(&mut segments, &mut queue, next, direction));
+                    }
                 }
+                break;
             }
+
+            segments[segment] = Segment { tiles, successors };
         }
 
-        visited.len()
+        (segments, entry_segments)
     }
 }
 
-impl SolutionGold<usize, usize> for Day {
-    fn calculate_gold(input: &str) -> usize {
-        let grid = input.as_bytes().split(|b| *b == b'\n').collect::<Vec<_>>();
-
-        (0..grid.len())
-            .map(|y| ((0, y), (1, 0)))
-            .chain((0..grid.len()).map(|y| ((grid[0].len() - 1, y), (-1, 0))))
-            .chain((0..grid[0].len()).map(|x| ((x, 0), (0, 1))))
-            .chain((0..grid[0].len()).map(|x| ((x, grid.len() - 1), (0, -1))))
-            .map(|(pos, dir)| {
-                let mut queue = VecDeque::<((isize, isize), (isize, isize))>::new();
-                queue.push_back(((pos.0 as isize, pos.1 as isize), dir));
-
-                // visited is a hasmap instead of a hashset so we can more easily track visited
-                // positions later
-                let mut visited = AHashMap::<(isize, isize), usize>::new();
-
-                while let Some((pos, dir)) = queue.pop_front() {
-                    if pos.0 < 0
-                        || pos.1 < 0
-                        || pos.0 >= grid[0].len() as isize
-                        || pos.1 >= grid.len() as isize
-                    {
-                        continue;
-                    }
+/// Tarjan's algorithm, without recursion. Returns the components in reverse topological order.
+fn strongly_connected_components(segments: &[Segment]) -> Vec<Vec<usize>> {
+    let n = segments.len();
+    let mut index = vec![usize::MAX; n];
+    let mut low = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = vec![];
+    let mut components = vec![];
+    let mut counter = 0;
 
-                    let dir_bitmap = match dir {
-                        (0, 1) => 1 << 0,
-                        (0, -1) => 1 << 1,
-                        (-1, 0) => 1 << 2,
-                        (1, 0) => 1 << 3,
-                        _ => unreachable!("unknown dir: {:?}", dir),
-                    };
-                    let entry = visited.entry(pos).or_default();
-                    if *entry & dir_bitmap != 0 {
-                        // already visited
-                        continue;
-                    }
-                    *entry |= dir_bitmap;
+    for root in 0..n {
+        if index[root] != usize::MAX {
+            continue;
+        }
 
-                    match (grid[pos.1 as usize][pos.0 as usize], dir) {
-                        (b'.', _) | (b'-', (_, 0)) | (b'|', (0, _)) => {
-                            queue.push_back(((pos.0 + dir.0, pos.1 + dir.1), dir));
-                        }
-                        (b'/', (0, 1)) | (b'\\', (0, -1)) => {
-                            queue.push_back(((pos.0 - 1, pos.1), (-1, 0)));
-                        }
-                        (b'/', (0, -1)) | (b'\\', (0, 1)) => {
-                            queue.push_back(((pos.0 + 1, pos.1), (1, 0)));
-                        }
-                        (b'/', (1, 0)) | (b'\\', (-1, 0)) => {
-                            queue.push_back(((pos.0, pos.1 - 1), (0, -1)));
-                        }
-                        (b'/', (-1, 0)) | (b'\\', (1, 0)) => {
-                            queue.push_back(((pos.0, pos.1 + 1), (0, 1)));
-                        }
-                        (b'-', (0, _)) => {
-                            queue.push_back(((pos.0 + 1, pos.1), (1, 0)));
-                            queue.push_back(((pos.0 - 1, pos.1), (-1, 0)));
-                        }
-                        (b'|', (_, 0)) => {
-                            queue.push_back(((pos.0, pos.1 + 1), (0, 1)));
-                            queue.push_back(((pos.0, pos.1 - 1), (0, -1)));
-                        }
-                        (c, _) => {
-                            unreachable!("unknown char: {c}");
-                        }
+        let mut call_stack = vec![(root, 0)];
+        while let Some((v, i)) = call_stack.pop() {
+            if i == 0 {
+                index[v] = counter;
+                low[v] = counter;
+                counter += 1;
+                stack.push(v);
+                on_stack[v] = true;
+            }
+
+            if let Some(&w) = segments[v].successors.get(i) {
+                call_stack.push((v, i + 1));
+                if index[w] == usize::MAX {
+                    call_stack.push((w, 0));
+                } else if on_stack[w] {
+                    low[v] = low[v].min(index[w]);
+                }
+                continue;
+            }
+
+            if low[v] == index[v] {
+                let mut component = vec![];
+                loop {
+                    let w = stack.pop().unwrap();
+                    on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
                     }
                 }
+                components.push(component);
+            }
+            if let Some(&(parent, _)) = call_stack.last() {
+                low[parent] = low[parent].min(low[v]);
+            }
+        }
+    }
 
-                visited.len()
-            })
-            .max()
-            .unwrap()
+    components
+}
+
+#[test]
+fn test_best_entry() {
+    let contraption = Contraption::parse(Day::INPUT_SAMPLE);
+    assert_eq!(
+        Entry {
+            position: (3, 0),
+            direction: Direction::Down,
+            energized: 51,
+        },
+        contraption.best_entry()
+    );
+
+    // no entry does better when traced on its own
+    let energized = |(position, direction)| {
+        let traced = contraption.trace(position, direction);
+        traced.iter().filter(|dirs| **dirs != 0).count()
+    };
+    assert!(contraption
+        .edge_entries()
+        .all(|entry| energized(entry) <= 51));
+
+    let contraption = Contraption::parse(Day::INPUT_REAL);
+    let best = contraption.best_entry();
+    assert_eq!(7896, best.energized);
+    let traced = contraption.trace(best.position, best.direction);
+    assert_eq!(7896, traced.iter().filter(|dirs| **dirs != 0).count());
+}
+
+#[test]
+fn test_render_heatmap() {
+    let contraption = Contraption::parse(".|.\n...\n.-.");
+    let visited = contraption.trace((0, 0), Direction::Right);
+    assert_eq!(
+        "1|.\n.1.\n1-1\n",
+        contraption.render_heatmap(&visited, false)
+    );
+
+    let colored = contraption.render_heatmap(&visited, true);
+    assert!(colored.starts_with("\x1b[30;44m.|\x1b[2m.\x1b[0m\n"));
+    assert_eq!(3, colored.matches("\x1b[0m\n").count());
+
+    let ppm = contraption.heatmap_ppm(&visited);
+    assert!(ppm.starts_with(b"P6\n9 9\n255\n"));
+    assert_eq!(b"P6\n9 9\n255\n".len() + 9 * 9 * 3, ppm.len());
+}
+
+#[test]
+fn test_animation_frames() {
+    let contraption = Contraption::parse(".|.\n...\n.-.");
+    let frames = contraption.animation_frames((0, 0), Direction::Right, false);
+    assert_eq!(
+        vec![
+            ">|.\n...\n.-.\n",
+            "1>.\n...\n.-.\n",
+            "1|.\n.v.\n.-.\n",
+            "1|.\n.1.\n.v.\n",
+            "1|.\n.1.\n<->\n",
+        ],
+        frames
+    );
+
+    // the layers add up to a full trace
+    let contraption = Contraption::parse(Day::INPUT_SAMPLE);
+    let mut visited = vec![0u8; contraption.grid.len()];
+    for layer in contraption.trace_layers((0, 0), Direction::Right) {
+        for ((x, y), direction) in layer {
+            assert_eq!(0, visited[x + y * contraption.width] & direction.bit());
+            visited[x + y * contraption.width] |= direction.bit();
+        }
     }
+    assert_eq!(contraption.trace((0, 0), Direction::Right), visited);
 }
 
 #[test]
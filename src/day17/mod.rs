@@ -12,264 +12,612 @@ impl SolutionSilver<usize> for Day {
     const INPUT_REAL: &'static str = include_str!("input_real.txt");
 
     fn calculate_silver(input: &str) -> usize {
-        const MAX_FLOW: usize = 3;
-
-        let grid = input.as_bytes().split(|b| *b == b'\n').collect::<Vec<_>>();
-        let width = grid[0].len();
-        let height = grid.len();
-
-        let mut heap = BinaryHeap::new();
-        heap.push(PositionWithDirection {
-            position: (1, 0),
-            direction: (1, 0),
-            flow_count: 1,
-            heat_loss: grid[0][1] as usize - b'0' as usize,
-        });
-        heap.push(PositionWithDirection {
-            position: (0, 1),
-            direction: (0, 1),
-            flow_count: 1,
-            heat_loss: grid[1][0] as usize - b'0' as usize,
-        });
-
-        let mut visited = AHashMap::new();
-
-        let mut best_result = usize::MAX;
-        while let Some(pos) = heap.pop() {
-            if pos.heat_loss >= best_result {
-                continue;
-            }
+        let map = HeatLossMap::parse(input);
+        let end = (map.width - 1, map.height - 1);
+        map.find_path((0, 0), end, &CrucibleRules::CRUCIBLE)
+            .unwrap()
+            .heat_loss
+    }
+}
 
-            if let Some(visited_heat_loss) =
-                visited.get(&(pos.position, pos.direction, pos.flow_count))
-            {
-                if *visited_heat_loss <= pos.heat_loss {
-                    continue;
-                }
-            }
+impl SolutionGold<usize, usize> for Day {
+    fn calculate_gold(input: &str) -> usize {
+        let map = HeatLossMap::parse(input);
+        let end = (map.width - 1, map.height - 1);
+        map.find_path((0, 0), end, &CrucibleRules::ULTRA_CRUCIBLE)
+            .unwrap()
+            .heat_loss
+    }
+}
 
-            visited.insert((pos.position, pos.direction, pos.flow_count), pos.heat_loss);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
 
-            if pos.position.0 == width - 1 && pos.position.1 == height - 1 {
-                // found the end!
-                best_result = std::cmp::min(best_result, pos.heat_loss);
-                continue;
+/// How a crucible is allowed to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrucibleRules {
+    /// The least amount of blocks the crucible has to move in a straight line before it can turn
+    /// or stop.
+    pub min_run: usize,
+    /// The most amount of blocks the crucible can move in a straight line.
+    pub max_run: usize,
+    /// Extra heat loss for every turn.
+    pub turn_cost: usize,
+}
+
+/// Moving `steps` blocks in a straight line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub direction: Direction,
+    pub steps: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CruciblePath {
+    pub start: (usize, usize),
+    pub heat_loss: usize,
+    pub moves: Vec<Move>,
+}
+
+pub struct HeatLossMap {
+    pub width: usize,
+    pub height: usize,
+    heat_loss: Vec<u8>,
+}
+
+/// The axis a crucible moved along to reach a block. It has to turn onto the other axis next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+/// A crucible at `position`, after moving `run` blocks in `direction`. The direction is `None`
+/// before the first move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct CrucibleState {
+    position: (usize, usize),
+    direction: Option<Direction>,
+    run: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct QueueEntry {
+    heat_loss: usize,
+    state: CrucibleState,
+}
+
+impl Direction {
+    const ALL: [Self; 4] = [Self::Up, Self::Down, Self::Left, Self::Right];
+
+    fn offset(self) -> (isize, isize) {
+        match self {
+            Self::Up => (0, -1),
+            Self::Down => (0, 1),
+            Self::Left => (-1, 0),
+            Self::Right => (1, 0),
+        }
+    }
+
+    fn opposite(self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+
+    fn axis(self) -> Axis {
+        match self {
+            Self::Up | Self::Down => Axis::Vertical,
+            Self::Left | Self::Right => Axis::Horizontal,
+        }
+    }
+
+    fn arrow(self) -> char {
+        match self {
+            Self::Up => '^',
+            Self::Down => 'v',
+            Self::Left => '<',
+            Self::Right => '>',
+        }
+    }
+}
+
+impl CrucibleRules {
+    pub const CRUCIBLE: Self = Self {
+        min_run: 1,
+        max_run: 3,
+        turn_cost: 0,
+    };
+
+    pub const ULTRA_CRUCIBLE: Self = Self {
+        min_run: 4,
+        max_run: 10,
+        turn_cost: 0,
+    };
+}
+
+impl CruciblePath {
+    /// Returns every block the crucible passes through, including the start.
+    pub fn positions(&self) -> Vec<(usize, usize)> {
+        let mut position = self.start;
+        let mut positions = vec![position];
+        for m in &self.moves {
+            let (dx, dy) = m.direction.offset();
+            for _ in 0..m.steps {
+                position = (
+                    position.0.wrapping_add_signed(dx),
+                    position.1.wrapping_add_signed(dy),
+                );
+                positions.push(position);
             }
+        }
+        positions
+    }
+}
+
+impl HeatLossMap {
+    pub fn parse(input: &str) -> Self {
+        let width = input.lines().next().unwrap().len();
+        let height = input.lines().count();
+        let heat_loss = input
+            .lines()
+            .flat_map(str::bytes)
+            .map(|b| b - b'0')
+            .collect();
+
+        Self {
+            width,
+            height,
+            heat_loss,
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> usize {
+        self.heat_loss[x + y * self.width] as usize
+    }
+
+    fn step(&self, position: (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+        let (dx, dy) = direction.offset();
+        let x = position.0.checked_add_signed(dx)?;
+        let y = position.1.checked_add_signed(dy)?;
+        (x < self.width && y < self.height).then_some((x, y))
+    }
+
+    /// Finds the path from `start` to `end` with the least heat loss. The heat loss of the start
+    /// block isn't counted, as the crucible starts there.
+    ///
+    /// Each state is a block along with the axis the crucible last moved along, and each edge is a
+    /// whole straight run followed by a turn. Edges cost at most `9 * max_run + turn_cost`, so the
+    /// states are kept in a ring of that many buckets, one per heat loss (Dial's algorithm).
+    pub fn find_path(
+        &self,
+        start: (usize, usize),
+        end: (usize, usize),
+        rules: &CrucibleRules,
+    ) -> Option<CruciblePath> {
+        if start == end {
+            return Some(CruciblePath {
+                start,
+                heat_loss: 0,
+                moves: vec![],
+            });
+        }
+
+        // every run pays the turn cost, including the first one, which is taken off at the end
+        let state_count = self.heat_loss.len() * 2;
+        let mut heat_losses = vec![usize::MAX; state_count];
+        let mut previous = vec![None; state_count];
 
-            if pos.flow_count < MAX_FLOW {
-                let next_x = pos.position.0 as isize + pos.direction.0;
-                let next_y = pos.position.1 as isize + pos.direction.1;
-                if next_x >= 0
-                    && next_y >= 0
-                    && (next_x as usize) < width
-                    && (next_y as usize) < height
-                {
-                    let next_x = next_x as usize;
-                    let next_y = next_y as usize;
-                    let next_pos = PositionWithDirection {
-                        position: (next_x, next_y),
-                        direction: pos.direction,
-                        flow_count: pos.flow_count + 1,
-                        heat_loss: pos.heat_loss + grid[next_y][next_x] as usize - b'0' as usize,
-                    };
-                    heap.push(next_pos);
+        let bucket_count = 9 * rules.max_run + rules.turn_cost + 1;
+        let mut buckets = vec![Vec::new(); bucket_count];
+        let mut queued = 0;
+
+        let start_index = start.0 + start.1 * self.width;
+        for axis in [Axis::Horizontal, Axis::Vertical] {
+            heat_losses[start_index * 2 + axis as usize] = 0;
+            buckets[0].push(start_index * 2 + axis as usize);
+            queued += 1;
+        }
+
+        let mut heat_loss = 0;
+        while queued > 0 {
+            // runs through blocks without heat loss add to the bucket that's being emptied, so
+            // keep taking from it until it's really empty
+            while let Some(state) = buckets[heat_loss % bucket_count].pop() {
+                queued -= 1;
+                if heat_losses[state] != heat_loss {
+                    // already found a better way here
+                    continue;
                 }
-            }
 
-            // split left and right
-            // (0, 1) -> (-1, 0), (1, 0)
-            // (1, 0) -> (0, -1), (0, 1)
-            let left = (-pos.direction.1, pos.direction.0);
-            let right = (pos.direction.1, -pos.direction.0);
-
-            // left
-            {
-                let next_x = pos.position.0 as isize + left.0;
-                let next_y = pos.position.1 as isize + left.1;
-                if next_x >= 0
-                    && next_y >= 0
-                    && (next_x as usize) < width
-                    && (next_y as usize) < height
-                {
-                    let next_x = next_x as usize;
-                    let next_y = next_y as usize;
-                    let next_pos = PositionWithDirection {
-                        position: (next_x, next_y),
-                        direction: left,
-                        flow_count: 1,
-                        heat_loss: pos.heat_loss + grid[next_y][next_x] as usize - b'0' as usize,
-                    };
-                    heap.push(next_pos);
+                let position = (state / 2 % self.width, state / 2 / self.width);
+                if position == end {
+                    return Some(self.reconstruct_run_path(start, state, rules, &previous));
                 }
-            }
 
-            // right
-            {
-                let next_x = pos.position.0 as isize + right.0;
-                let next_y = pos.position.1 as isize + right.1;
-                if next_x >= 0
-                    && next_y >= 0
-                    && (next_x as usize) < width
-                    && (next_y as usize) < height
-                {
-                    let next_x = next_x as usize;
-                    let next_y = next_y as usize;
-                    let next_pos = PositionWithDirection {
-                        position: (next_x, next_y),
-                        direction: right,
-                        flow_count: 1,
-                        heat_loss: pos.heat_loss + grid[next_y][next_x] as usize - b'0' as usize,
-                    };
-                    heap.push(next_pos);
+                let directions = match state % 2 {
+                    0 => [Direction::Up, Direction::Down],
+                    _ => [Direction::Left, Direction::Right],
+                };
+                for direction in directions {
+                    let mut next_position = position;
+                    let mut next_heat_loss = heat_loss + rules.turn_cost;
+                    for steps in 1..=rules.max_run {
+                        let Some(p) = self.step(next_position, direction) else {
+                            break;
+                        };
+                        next_position = p;
+                        next_heat_loss += self.get(p.0, p.1);
+
+                        let next = (p.0 + p.1 * self.width) * 2 + direction.axis() as usize;
+                        if steps >= rules.min_run && next_heat_loss < heat_losses[next] {
+                            heat_losses[next] = next_heat_loss;
+                            previous[next] = Some((state, Move { direction, steps }));
+                            buckets[next_heat_loss % bucket_count].push(next);
+                            queued += 1;
+                        }
+                    }
                 }
             }
+
+            heat_loss += 1;
         }
 
-        best_result
+        None
     }
-}
 
-impl SolutionGold<usize, usize> for Day {
-    fn calculate_gold(input: &str) -> usize {
-        const MIN_FLOW: usize = 4;
-        const MAX_FLOW: usize = 10;
-
-        let grid = input.as_bytes().split(|b| *b == b'\n').collect::<Vec<_>>();
-        let width = grid[0].len();
-        let height = grid.len();
-
-        let mut heap = BinaryHeap::new();
-        heap.push(PositionWithDirection {
-            position: (1, 0),
-            direction: (1, 0),
-            flow_count: 1,
-            heat_loss: grid[0][1] as usize - b'0' as usize,
-        });
-        heap.push(PositionWithDirection {
-            position: (0, 1),
-            direction: (0, 1),
-            flow_count: 1,
-            heat_loss: grid[1][0] as usize - b'0' as usize,
-        });
-
-        let mut visited = AHashMap::new();
-
-        let mut best_result = usize::MAX;
-        while let Some(pos) = heap.pop() {
-            if pos.heat_loss >= best_result {
-                continue;
-            }
+    fn reconstruct_run_path(
+        &self,
+        start: (usize, usize),
+        end: usize,
+        rules: &CrucibleRules,
+        previous: &[Option<(usize, Move)>],
+    ) -> CruciblePath {
+        let mut moves = vec![];
+        let mut heat_loss = 0;
+        let mut state = end;
+        while let Some((previous_state, m)) = previous[state] {
+            moves.push(m);
+            state = previous_state;
+        }
+        moves.reverse();
 
-            if let Some(visited_heat_loss) =
-                visited.get(&(pos.position, pos.direction, pos.flow_count))
-            {
-                if *visited_heat_loss <= pos.heat_loss {
-                    continue;
-                }
+        let mut position = start;
+        for m in &moves {
+            for _ in 0..m.steps {
+                position = self.step(position, m.direction).unwrap();
+                heat_loss += self.get(position.0, position.1);
             }
+        }
 
-            visited.insert((pos.position, pos.direction, pos.flow_count), pos.heat_loss);
+        CruciblePath {
+            start,
+            heat_loss: heat_loss + (moves.len() - 1) * rules.turn_cost,
+            moves,
+        }
+    }
 
-            if pos.position.0 == width - 1 && pos.position.1 == height - 1 {
-                // found the end!
-                best_result = std::cmp::min(best_result, pos.heat_loss);
+    /// The original search over single steps, using a [`BinaryHeap`] and a hash map of visited
+    /// states. Kept to check and benchmark [`Self::find_path`] against.
+    pub fn find_path_binary_heap(
+        &self,
+        start: (usize, usize),
+        end: (usize, usize),
+        rules: &CrucibleRules,
+    ) -> Option<CruciblePath> {
+        let start_state = CrucibleState {
+            position: start,
+            direction: None,
+            run: 0,
+        };
+
+        let mut heap = BinaryHeap::from([QueueEntry {
+            heat_loss: 0,
+            state: start_state,
+        }]);
+        let mut visited = AHashMap::from([(start_state, 0)]);
+        let mut previous = AHashMap::new();
+
+        while let Some(QueueEntry { heat_loss, state }) = heap.pop() {
+            if visited[&state] < heat_loss {
+                // already found a better way here
                 continue;
             }
 
-            // go straight
-            if pos.flow_count < MAX_FLOW {
-                let next_x = pos.position.0 as isize + pos.direction.0;
-                let next_y = pos.position.1 as isize + pos.direction.1;
-                if next_x >= 0
-                    && next_y >= 0
-                    && (next_x as usize) < width
-                    && (next_y as usize) < height
-                {
-                    let next_x = next_x as usize;
-                    let next_y = next_y as usize;
-                    let next_pos = PositionWithDirection {
-                        position: (next_x, next_y),
-                        direction: pos.direction,
-                        flow_count: pos.flow_count + 1,
-                        heat_loss: pos.heat_loss + grid[next_y][next_x] as usize - b'0' as usize,
-                    };
-                    heap.push(next_pos);
-                }
+            if state.position == end && (state.direction.is_none() || state.run >= rules.min_run) {
+                // found the end!
+                return Some(self.reconstruct_path(start, heat_loss, state, &previous));
             }
 
-            // split left and right
-            // (0, 1) -> (-1, 0), (1, 0)
-            // (1, 0) -> (0, -1), (0, 1)
-            let left = (-pos.direction.1, pos.direction.0);
-            let right = (pos.direction.1, -pos.direction.0);
-
-            // left
-            if pos.flow_count >= MIN_FLOW {
-                let next_x = pos.position.0 as isize + left.0;
-                let next_y = pos.position.1 as isize + left.1;
-                if next_x >= 0
-                    && next_y >= 0
-                    && (next_x as usize) < width
-                    && (next_y as usize) < height
-                {
-                    let next_x = next_x as usize;
-                    let next_y = next_y as usize;
-                    let next_pos = PositionWithDirection {
-                        position: (next_x, next_y),
-                        direction: left,
-                        flow_count: 1,
-                        heat_loss: pos.heat_loss + grid[next_y][next_x] as usize - b'0' as usize,
-                    };
-                    heap.push(next_pos);
+            for direction in Direction::ALL {
+                let (run, extra_cost) = match state.direction {
+                    None => (1, 0),
+                    Some(d) if d == direction => (state.run + 1, 0),
+                    Some(d) if d != direction.opposite() && state.run >= rules.min_run => {
+                        (1, rules.turn_cost)
+                    }
+                    Some(_) => continue,
+                };
+                if run > rules.max_run {
+                    continue;
+                }
+                let Some(position) = self.step(state.position, direction) else {
+                    continue;
+                };
+
+                let next = CrucibleState {
+                    position,
+                    direction: Some(direction),
+                    run,
+                };
+                let next_heat_loss = heat_loss + extra_cost + self.get(position.0, position.1);
+                if visited.get(&next).is_none_or(|h| *h > next_heat_loss) {
+                    visited.insert(next, next_heat_loss);
+                    previous.insert(next, state);
+                    heap.push(QueueEntry {
+                        heat_loss: next_heat_loss,
+                        state: next,
+                    });
                 }
             }
+        }
 
-            // right
-            if pos.flow_count >= MIN_FLOW {
-                let next_x = pos.position.0 as isize + right.0;
-                let next_y = pos.position.1 as isize + right.1;
-                if next_x >= 0
-                    && next_y >= 0
-                    && (next_x as usize) < width
-                    && (next_y as usize) < height
-                {
-                    let next_x = next_x as usize;
-                    let next_y = next_y as usize;
-                    let next_pos = PositionWithDirection {
-                        position: (next_x, next_y),
-                        direction: right,
-                        flow_count: 1,
-                        heat_loss: pos.heat_loss + grid[next_y][next_x] as usize - b'0' as usize,
-                    };
-                    heap.push(next_pos);
-                }
+        None
+    }
+
+    fn reconstruct_path(
+        &self,
+        start: (usize, usize),
+        heat_loss: usize,
+        end: CrucibleState,
+        previous: &AHashMap<CrucibleState, CrucibleState>,
+    ) -> CruciblePath {
+        let mut moves = Vec::<Move>::new();
+        let mut state = end;
+        while let Some(direction) = state.direction {
+            match moves.last_mut() {
+                Some(m) if m.direction == direction => m.steps += 1,
+                _ => moves.push(Move {
+                    direction,
+                    steps: 1,
+                }),
             }
+            state = previous[&state];
         }
+        moves.reverse();
 
-        best_result
+        CruciblePath {
+            start,
+            heat_loss,
+            moves,
+        }
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct PositionWithDirection {
-    position: (usize, usize),
-    direction: (isize, isize),
-    flow_count: usize,
-    heat_loss: usize,
+    /// Draws the map with the path drawn over it as arrows, like in the puzzle description.
+    pub fn render_path(&self, path: &CruciblePath) -> String {
+        let mut grid = self
+            .heat_loss
+            .iter()
+            .map(|h| (b'0' + h) as char)
+            .collect::<Vec<_>>();
+
+        let mut position = path.start;
+        for m in &path.moves {
+            for _ in 0..m.steps {
+                position = self.step(position, m.direction).unwrap();
+                grid[position.0 + position.1 * self.width] = m.direction.arrow();
+            }
+        }
+
+        let mut output = String::with_capacity(grid.len() + self.height);
+        for row in grid.chunks(self.width) {
+            output.extend(row);
+            output.push('\n');
+        }
+        output
+    }
 }
 
-impl PartialOrd for PositionWithDirection {
+impl PartialOrd for QueueEntry {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for PositionWithDirection {
+impl Ord for QueueEntry {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.heat_loss.cmp(&other.heat_loss).reverse()
     }
 }
 
+#[cfg(test)]
+fn check_path(map: &HeatLossMap, path: &CruciblePath, rules: &CrucibleRules) {
+    let positions = path.positions();
+    let heat_loss = positions[1..]
+        .iter()
+        .map(|&(x, y)| map.get(x, y))
+        .sum::<usize>();
+    let turns = path.moves.len().saturating_sub(1);
+    assert_eq!(path.heat_loss, heat_loss + turns * rules.turn_cost);
+
+    for (i, m) in path.moves.iter().enumerate() {
+        assert!((rules.min_run..=rules.max_run).contains(&m.steps), "{m:?}");
+        if i > 0 {
+            assert_ne!(path.moves[i - 1].direction.opposite(), m.direction);
+        }
+    }
+}
+
+#[test]
+fn test_find_path() {
+    let map = HeatLossMap::parse(Day::INPUT_SAMPLE);
+    let end = (map.width - 1, map.height - 1);
+
+    let path = map
+        .find_path((0, 0), end, &CrucibleRules::CRUCIBLE)
+        .unwrap();
+    assert_eq!(102, path.heat_loss);
+    assert_eq!(end, *path.positions().last().unwrap());
+    check_path(&map, &path, &CrucibleRules::CRUCIBLE);
+
+    let rendered = map.render_path(&path);
+    assert_eq!(map.height, rendered.lines().count());
+    assert_eq!(
+        path.positions().len() - 1,
+        rendered.matches(['^', 'v', '<', '>']).count()
+    );
+
+    // going backwards
+    let path = map
+        .find_path(end, (0, 0), &CrucibleRules::CRUCIBLE)
+        .unwrap();
+    check_path(&map, &path, &CrucibleRules::CRUCIBLE);
+
+    let path = map
+        .find_path((3, 3), (3, 3), &CrucibleRules::CRUCIBLE)
+        .unwrap();
+    assert_eq!(0, path.heat_loss);
+    assert!(path.moves.is_empty());
+}
+
+#[test]
+fn test_find_path_ultra_crucible() {
+    let map = HeatLossMap::parse(
+        "\
+111111111111
+999999999991
+999999999991
+999999999991
+999999999991",
+    );
+    let rules = CrucibleRules::ULTRA_CRUCIBLE;
+    let path = map.find_path((0, 0), (11, 4), &rules).unwrap();
+    assert_eq!(71, path.heat_loss);
+    assert_eq!(
+        vec![
+            Move {
+                direction: Direction::Right,
+                steps: 7
+            },
+            Move {
+                direction: Direction::Down,
+                steps: 4
+            },
+            Move {
+                direction: Direction::Right,
+                steps: 4
+            },
+        ],
+        path.moves
+    );
+    check_path(&map, &path, &rules);
+
+    // too short to ever stop
+    let map = HeatLossMap::parse("111");
+    assert_eq!(None, map.find_path((0, 0), (2, 0), &rules));
+}
+
+#[test]
+fn test_find_path_turn_cost() {
+    let map = HeatLossMap::parse("111\n111\n111");
+    let rules = CrucibleRules {
+        turn_cost: 0,
+        ..CrucibleRules::CRUCIBLE
+    };
+    assert_eq!(4, map.find_path((0, 0), (2, 2), &rules).unwrap().heat_loss);
+
+    let rules = CrucibleRules {
+        turn_cost: 10,
+        ..CrucibleRules::CRUCIBLE
+    };
+    let path = map.find_path((0, 0), (2, 2), &rules).unwrap();
+    assert_eq!(14, path.heat_loss);
+    assert_eq!(2, path.moves.len());
+    check_path(&map, &path, &rules);
+}
+
+#[test]
+fn test_find_path_matches_binary_heap() {
+    let map = HeatLossMap::parse("100\n100\n111");
+    for rules in [CrucibleRules::CRUCIBLE, CrucibleRules::ULTRA_CRUCIBLE] {
+        assert_eq!(
+            map.find_path_binary_heap((0, 0), (2, 2), &rules)
+                .map(|p| p.heat_loss),
+            map.find_path((0, 0), (2, 2), &rules).map(|p| p.heat_loss),
+        );
+    }
+    assert_eq!(
+        Some(1),
+        map.find_path((0, 0), (2, 2), &CrucibleRules::CRUCIBLE)
+            .map(|p| p.heat_loss)
+    );
+
+    let mut state = 0x2545f4914f6cdd1d;
+    let mut random = || crate::utils::xorshift64(&mut state) as usize;
+    for _ in 0..50 {
+        let width = 1 + random() % 8;
+        let height = 1 + random() % 8;
+        let input = (0..height)
+            .map(|_| {
+                (0..width)
+                    .map(|_| (b'1' + (random() % 9) as u8) as char)
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let map = HeatLossMap::parse(&input);
+
+        // blocks without heat loss make runs that cost nothing
+        let zeros = input.replace(['1', '2', '3', '4', '5'], "0");
+        let zeros_map = HeatLossMap::parse(&zeros);
+
+        let start = (0, random() % height);
+        let end = (width - 1, height - 1);
+        for rules in [
+            CrucibleRules::CRUCIBLE,
+            CrucibleRules::ULTRA_CRUCIBLE,
+            CrucibleRules {
+                min_run: 2,
+                max_run: 3,
+                turn_cost: 5,
+            },
+        ] {
+            for (input, map) in [(&input, &map), (&zeros, &zeros_map)] {
+                let path = map.find_path(start, end, &rules);
+                let expected = map.find_path_binary_heap(start, end, &rules);
+                assert_eq!(
+                    expected.as_ref().map(|p| p.heat_loss),
+                    path.as_ref().map(|p| p.heat_loss),
+                    "{input}\n{rules:?} {start:?}"
+                );
+                if let Some(path) = path {
+                    check_path(map, &path, &rules);
+                }
+            }
+        }
+
+        let rules = CrucibleRules {
+            turn_cost: 0,
+            ..CrucibleRules::CRUCIBLE
+        };
+        assert_eq!(
+            zeros_map
+                .find_path_binary_heap(start, end, &rules)
+                .map(|p| p.heat_loss),
+            zeros_map.find_path(start, end, &rules).map(|p| p.heat_loss),
+            "{zeros}\n{rules:?} {start:?}"
+        );
+    }
+}
+
 #[test]
 fn test_silver_sample() {
     let output = Day::calculate_silver(Day::INPUT_SAMPLE);
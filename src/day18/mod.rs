@@ -1,8 +1,4 @@
-#![allow(clippy::range_minus_one, clippy::redundant_clone)]
-
-use std::{collections::VecDeque, ops::RangeInclusive};
-
-use ahash::AHashSet;
+use std::fmt::Write;
 
 use super::*;
 
@@ -14,294 +10,172 @@ impl SolutionSilver<usize> for Day {
     const INPUT_REAL: &'static str = include_str!("input_real.txt");
 
     fn calculate_silver(input: &str) -> usize {
-        let mut map = AHashSet::new();
-        let mut pos = (0, 0);
-        map.insert(pos);
-
-        input.lines().for_each(|line| {
-            let (dir, line) = line.split_once(' ').unwrap();
-            let (len, _code) = line.split_once(' ').unwrap();
-
-            let direction = match dir {
-                "U" => (0, -1),
-                "D" => (0, 1),
-                "L" => (-1, 0),
-                "R" => (1, 0),
-                _ => panic!("Unknown direction {}", dir),
-            };
-
-            let len = len.parse::<usize>().unwrap();
-
-            for _ in 0..len {
-                pos.0 += direction.0;
-                pos.1 += direction.1;
-                map.insert(pos);
-            }
-        });
-
-        // flood-fill all
-        let min_x = *map.iter().map(|(x, _)| x).min().unwrap();
-        let min_y = *map.iter().map(|(_, y)| y).min().unwrap();
-        let max_x = *map.iter().map(|(x, _)| x).max().unwrap();
-        let max_y = *map.iter().map(|(_, y)| y).max().unwrap();
-
-        let mut visited_outside = AHashSet::<(i32, i32)>::new();
-
-        for start_y in min_y..=max_y {
-            for start_x in min_x..=max_x {
-                // ignore items already visited
-                if map.contains(&(start_x, start_y)) {
-                    continue;
-                }
-                if visited_outside.contains(&(start_x, start_y)) {
-                    continue;
-                }
-
-                // flood-fill until escaped area or exhausted all possibilities
-                let mut visited_now = AHashSet::new();
-                let mut queue = VecDeque::new();
-                queue.push_back((start_x, start_y));
-
-                let mut escaped = false;
-                while let Some((x, y)) = queue.pop_front() {
-                    // ignore items already visited
-                    if map.contains(&(x, y)) {
-                        continue;
-                    }
-                    if visited_now.contains(&(x, y)) {
-                        continue;
-                    }
-                    if visited_outside.contains(&(x, y)) {
-                        escaped = true;
-                        break;
-                    }
-
-                    if x < min_x || x > max_x || y < min_y || y > max_y {
-                        // escaped area
-                        escaped = true;
-                        break;
-                    }
-
-                    // mark as visited
-                    visited_now.insert((x, y));
-
-                    // add neighbors to queue
-                    queue.push_back((x - 1, y));
-                    queue.push_back((x + 1, y));
-                    queue.push_back((x, y - 1));
-                    queue.push_back((x, y + 1));
-                }
-
-                if escaped {
-                    // TODO: needed for perf but wrong?
-                    visited_outside.extend(visited_now);
-                } else {
-                    map.extend(visited_now);
-                }
-            }
-        }
-
-        map.len()
+        DigPlan::parse(input, Encoding::Direction).volume() as usize
     }
 }
 
 impl SolutionGold<usize, usize> for Day {
     fn calculate_gold(input: &str) -> usize {
-        let mut x_positions = vec![];
-        let mut y_positions = vec![];
-        let mut lines = AHashSet::new();
-
-        let mut pos = (0, 0);
-        x_positions.push(pos.0);
-        y_positions.push(pos.1);
-
-        input.lines().for_each(|line| {
-            let (_dir, line) = line.split_once(' ').unwrap();
-            let (_len, code) = line.split_once(' ').unwrap();
-
-            let code = code
-                .trim_start_matches('(')
-                .trim_start_matches('#')
-                .trim_end_matches(')');
-
-            let len = i32::from_str_radix(&code.chars().take(5).collect::<String>(), 16).unwrap();
-
-            let dir = code.chars().last().unwrap();
-            let direction = match code.chars().last().unwrap() {
-                '0' => (1, 0),
-                '1' => (0, 1),
-                '2' => (-1, 0),
-                '3' => (0, -1),
-                _ => panic!("Unknown direction {}", dir),
-            };
-
-            let next_pos = (pos.0 + direction.0 * len, pos.1 + direction.1 * len);
-            x_positions.push(next_pos.0);
-            y_positions.push(next_pos.1);
-
-            // create list of lines
-            let min_x = pos.0.min(next_pos.0);
-            let max_x = pos.0.max(next_pos.0);
-            let min_y = pos.1.min(next_pos.1);
-            let max_y = pos.1.max(next_pos.1);
-
-            if min_x == max_x {
-                // vertical line
-                lines.insert(((min_x..=max_x), (min_y..=min_y)));
-                lines.insert(((min_x..=max_x), (min_y + 1..=max_y - 1)));
-                lines.insert(((min_x..=max_x), (max_y..=max_y)));
-            } else {
-                debug_assert_eq!(min_y, max_y);
-                // horizontal line
-                lines.insert(((min_x..=min_x), (min_y..=max_y)));
-                lines.insert(((min_x + 1..=max_x - 1), (min_y..=max_y)));
-                lines.insert(((max_x..=max_x), (min_y..=max_y)));
-            }
-
-            pos = next_pos;
-        });
-        x_positions.sort_unstable();
-        x_positions.dedup();
-        y_positions.sort_unstable();
-        y_positions.dedup();
+        DigPlan::parse(input, Encoding::Hex).volume() as usize
+    }
+}
 
-        // flood-fill all
-        let mut inside_shape = AHashSet::new();
-        let mut outside_shape = AHashSet::<(RangeInclusive<i32>, RangeInclusive<i32>)>::new();
-        let mut visited = AHashSet::new();
-        let mut queue = VecDeque::new();
-        for y_pos_idx in 0..(y_positions.len() - 1) {
-            let y_start = y_positions[y_pos_idx] + 1;
-            let y_end = y_positions[y_pos_idx + 1] - 1;
-            let y_range = y_start..=y_end;
-            debug_assert!(y_start < y_end);
+/// Which part of a dig plan line holds the direction and length of the trench.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// The `R 6` part.
+    Direction,
+    /// The `(#70c710)` part, with the length in the first 5 hex digits and the direction in the
+    /// last one.
+    Hex,
+}
 
-            for x_pos_idx in 0..(x_positions.len() - 1) {
-                let x_start = x_positions[x_pos_idx] + 1;
-                let x_end = x_positions[x_pos_idx + 1] - 1;
-                let x_range = x_start..=x_end;
-                debug_assert!(x_start < x_end);
+/// The trench dug by following a dig plan, as a closed polygon.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigPlan {
+    /// The corners of the trench, starting and ending at `(0, 0)`.
+    pub vertices: Vec<(i64, i64)>,
+    /// `colors[i]` is the colour of the edge from `vertices[i]` to `vertices[i + 1]`.
+    pub colors: Vec<u32>,
+}
 
-                // ignore items already visited
-                if inside_shape.contains(&(x_range.clone(), y_range.clone())) {
-                    continue;
+impl DigPlan {
+    pub fn parse(input: &str, encoding: Encoding) -> Self {
+        let mut position = (0, 0);
+        let mut vertices = vec![position];
+        let mut colors = vec![];
+
+        for line in input.lines() {
+            let mut parts = line.split_whitespace();
+            let (direction, length, code) = (
+                parts.next().unwrap(),
+                parts.next().unwrap(),
+                parts.next().unwrap(),
+            );
+            let code = code.trim_start_matches("(#").trim_end_matches(')');
+            let color = u32::from_str_radix(code, 16).unwrap();
+
+            let (direction, length) = match encoding {
+                Encoding::Direction => (direction.as_bytes()[0], length.parse::<i64>().unwrap()),
+                Encoding::Hex => {
+                    let direction = match color & 0xF {
+                        0 => b'R',
+                        1 => b'D',
+                        2 => b'L',
+                        3 => b'U',
+                        digit => panic!("Unknown direction {digit:x}"),
+                    };
+                    (direction, i64::from_str_radix(&code[..5], 16).unwrap())
                 }
-                if outside_shape.contains(&(x_range.clone(), y_range.clone())) {
-                    continue;
-                }
-
-                // flood-fill until escaped area or exhausted all possibilities
-                visited.clear();
-                queue.clear();
-                queue.push_back((x_pos_idx as isize, y_pos_idx as isize));
-
-                let mut escaped = false;
-                while let Some((x_pos_idx, y_pos_idx)) = queue.pop_front() {
-                    if x_pos_idx < 0
-                        || x_pos_idx >= (x_positions.len() as isize - 1)
-                        || y_pos_idx < 0
-                        || y_pos_idx >= (y_positions.len() as isize - 1)
-                    {
-                        // escaped area
-                        escaped = true;
-                        break;
-                    }
-
-                    // copy/paste, construct the ranges again
-                    let y_start = y_positions[y_pos_idx as usize] + 1;
-                    let y_end = y_positions[y_pos_idx as usize + 1] - 1;
-                    let y_range = y_start..=y_end;
-                    if y_start >= y_end {
-                        continue;
-                    }
-                    let x_start = x_positions[x_pos_idx as usize] + 1;
-                    let x_end = x_positions[x_pos_idx as usize + 1] - 1;
-                    let x_range = x_start..=x_end;
-                    if x_start >= x_end {
-                        continue;
-                    }
-                    debug_assert!(y_start < y_end);
-                    debug_assert!(x_start < x_end);
+            };
+            let (dx, dy) = match direction {
+                b'U' => (0, -1),
+                b'D' => (0, 1),
+                b'L' => (-1, 0),
+                b'R' => (1, 0),
+                _ => panic!("Unknown direction {}", direction as char),
+            };
 
-                    // ignore items already visited
-                    if inside_shape.contains(&(x_range.clone(), y_range.clone())) {
-                        escaped = false;
-                        break;
-                    }
-                    if outside_shape.contains(&(x_range.clone(), y_range.clone())) {
-                        escaped = true;
-                        break;
-                    }
-                    if visited.contains(&(x_range.clone(), y_range.clone())) {
-                        continue;
-                    }
+            position = (position.0 + dx * length, position.1 + dy * length);
+            vertices.push(position);
+            colors.push(color);
+        }
 
-                    // mark as visited
-                    visited.insert((x_range.clone(), y_range.clone()));
+        debug_assert_eq!(Some(&(0, 0)), vertices.last(), "trench isn't closed");
+        Self { vertices, colors }
+    }
 
-                    // add neighbors to queue, iff there is no wall blocking them
-                    let can_go_left = !lines
-                        .iter()
-                        .any(|r| r.0.contains(&(x_start - 1)) && r.1.contains(&y_start));
-                    let can_go_right = !lines
-                        .iter()
-                        .any(|r| r.0.contains(&(x_end + 1)) && r.1.contains(&y_start));
-                    let can_go_up = !lines
-                        .iter()
-                        .any(|r| r.1.contains(&(y_start - 1)) && r.0.contains(&x_start));
-                    let can_go_down = !lines
-                        .iter()
-                        .any(|r| r.1.contains(&(y_end + 1)) && r.0.contains(&x_start));
+    /// Returns the amount of cubic metres dug out, counting both the trench and the interior.
+    ///
+    /// The shoelace formula gives the area of the polygon through the centres of the trench, and
+    /// by Pick's theorem that area is `interior + boundary / 2 - 1`.
+    pub fn volume(&self) -> i64 {
+        let mut double_area = 0;
+        let mut boundary = 0;
+        for pair in self.vertices.windows(2) {
+            let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+            double_area += x1 * y2 - x2 * y1;
+            boundary += (x2 - x1).abs() + (y2 - y1).abs();
+        }
 
-                    if can_go_left {
-                        queue.push_back((x_pos_idx - 1, y_pos_idx));
-                    }
-                    if can_go_right {
-                        queue.push_back((x_pos_idx + 1, y_pos_idx));
-                    }
-                    if can_go_up {
-                        queue.push_back((x_pos_idx, y_pos_idx - 1));
-                    }
-                    if can_go_down {
-                        queue.push_back((x_pos_idx, y_pos_idx + 1));
-                    }
-                }
+        let interior = double_area.abs() / 2 - boundary / 2 + 1;
+        interior + boundary
+    }
 
-                if escaped {
-                    outside_shape.extend(visited.iter().cloned());
-                } else {
-                    inside_shape.extend(visited.iter().cloned());
-                }
-            }
+    /// Draws the trench as an SVG image, with each edge in the colour from the dig plan.
+    pub fn to_svg(&self) -> String {
+        let min_x = self.vertices.iter().map(|v| v.0).min().unwrap();
+        let max_x = self.vertices.iter().map(|v| v.0).max().unwrap();
+        let min_y = self.vertices.iter().map(|v| v.1).min().unwrap();
+        let max_y = self.vertices.iter().map(|v| v.1).max().unwrap();
+        let margin = ((max_x - min_x).max(max_y - min_y) / 50).max(1);
+
+        let mut output = String::new();
+        writeln!(
+            output,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+            min_x - margin,
+            min_y - margin,
+            max_x - min_x + 2 * margin,
+            max_y - min_y + 2 * margin,
+        )
+        .unwrap();
+        output.push_str(concat!(
+            "  <style>line { stroke-width: 2; stroke-linecap: square; ",
+            "vector-effect: non-scaling-stroke; }</style>\n",
+        ));
+        for (pair, color) in self.vertices.windows(2).zip(&self.colors) {
+            let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+            writeln!(
+                output,
+                r##"  <line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="#{color:06x}" />"##,
+            )
+            .unwrap();
         }
+        output.push_str("</svg>\n");
+
+        output
+    }
+}
 
-        let all_ranges = inside_shape
-            .iter()
-            .flat_map(|region| {
-                let left_edge = region.0.start() - 1..=region.0.start() - 1;
-                let right_edge = region.0.end() + 1..=region.0.end() + 1;
-                let top_edge = region.1.start() - 1..=region.1.start() - 1;
-                let bottom_edge = region.1.end() + 1..=region.1.end() + 1;
+#[test]
+fn test_dig_plan() {
+    let plan = DigPlan::parse(Day::INPUT_SAMPLE, Encoding::Direction);
+    assert_eq!(15, plan.vertices.len());
+    assert_eq!(&[(0, 0), (6, 0), (6, 5), (4, 5)], &plan.vertices[..4]);
+    assert_eq!(0x70c710, plan.colors[0]);
+    assert_eq!(62, plan.volume());
+
+    let plan = DigPlan::parse(Day::INPUT_SAMPLE, Encoding::Hex);
+    assert_eq!(&[(0, 0), (461937, 0), (461937, 56407)], &plan.vertices[..3]);
+    assert_eq!(0x70c710, plan.colors[0]);
+    assert_eq!(952408144115, plan.volume());
+
+    // a single metre, and a plan going counter-clockwise
+    let plan = DigPlan::parse("R 1 (#000000)\nL 1 (#000000)", Encoding::Direction);
+    assert_eq!(2, plan.volume());
+    let plan = DigPlan::parse(
+        "D 2 (#000000)\nR 2 (#000000)\nU 2 (#000000)\nL 2 (#000000)",
+        Encoding::Direction,
+    );
+    assert_eq!(9, plan.volume());
+}
 
-                [
-                    (region.0.clone(), region.1.clone()),      // inner
-                    (left_edge.clone(), region.1.clone()),     // left
-                    (right_edge.clone(), region.1.clone()),    // right
-                    (region.0.clone(), top_edge.clone()),      // top
-                    (region.0.clone(), bottom_edge.clone()),   // bottom
-                    (left_edge.clone(), top_edge.clone()),     // top-left
-                    (right_edge.clone(), top_edge.clone()),    // top-right
-                    (left_edge.clone(), bottom_edge.clone()),  // bottom-left
-                    (right_edge.clone(), bottom_edge.clone()), // bottom-right
-                ]
-            })
-            .collect::<AHashSet<_>>();
+#[test]
+#[should_panic(expected = "Unknown direction 4")]
+fn test_dig_plan_unknown_hex_direction() {
+    DigPlan::parse("R 6 (#70c714)", Encoding::Hex);
+}
 
-        all_ranges
-            .into_iter()
-            .map(|(range_x, range_y)| range_x.count() * range_y.count())
-            .sum::<usize>()
-    }
+#[test]
+fn test_to_svg() {
+    let plan = DigPlan::parse(Day::INPUT_SAMPLE, Encoding::Direction);
+    let svg = plan.to_svg();
+    assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-1 -1 8 11">"#));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(14, svg.matches("<line ").count());
+    assert!(svg.contains(r##"<line x1="0" y1="0" x2="6" y2="0" stroke="#70c710""##));
+    assert!(svg.contains(r##"stroke="#015232""##));
 }
 
 #[test]
@@ -0,0 +1,834 @@
+//! A compiler front end for the workflow language: a tokenizer and parser with positioned errors,
+//! a validator, optimization passes and a pretty-printer that writes the puzzle syntax back out.
+
+use std::{fmt::Display, ops::RangeInclusive};
+
+use ahash::{AHashMap, AHashSet};
+
+use super::regions::Ratings;
+use super::{Input, Output, Rule, Workflow};
+
+#[cfg(test)]
+use {super::Day, crate::SolutionSilver};
+
+/// The workflow every part starts in.
+pub const ENTRY: &str = "in";
+
+/// The range of each rating in the puzzle.
+pub const RATINGS: RangeInclusive<u16> = 1..=4000;
+
+/// A line and column in the source, both starting at 1. Columns count bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind<'a> {
+    Name(&'a str),
+    Number(u16),
+    LessThan,
+    GreaterThan,
+    Colon,
+    Comma,
+    OpenBrace,
+    CloseBrace,
+    Newline,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind<'a>,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileError {
+    pub position: Position,
+    pub kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnexpectedChar(char),
+    NumberTooLarge,
+    UnexpectedToken {
+        expected: &'static str,
+        found: String,
+    },
+    UnknownCategory(String),
+    DuplicateWorkflow(String),
+}
+
+/// A problem found by [`Program::validate`]. None of these stop a program from being parsed, but
+/// running it may panic or never finish.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic<'a> {
+    /// There is no [`ENTRY`] workflow.
+    MissingEntry,
+    UndefinedTarget {
+        workflow: &'a str,
+        target: &'a str,
+    },
+    /// Workflows that send parts around in a circle, in the order they send them.
+    Cycle(Vec<&'a str>),
+    /// A rule that can never match, because the rules before it already took all parts it would
+    /// match. `rule` is the amount of rules for the fallback.
+    UnreachableRule {
+        workflow: &'a str,
+        rule: usize,
+    },
+    /// A workflow no part can get to from [`ENTRY`].
+    UnreachableWorkflow(&'a str),
+}
+
+/// A list of workflows, in the order they were defined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program<'a> {
+    pub order: Vec<&'a str>,
+    pub workflows: AHashMap<&'a str, Workflow<'a>>,
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    index: usize,
+    /// The position right after the last character, for errors at the end of the input.
+    end: Position,
+}
+
+/// Splits the workflow section of the input into tokens.
+pub fn tokenize(source: &str) -> Result<Vec<Token<'_>>, CompileError> {
+    let bytes = source.as_bytes();
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut line_start = 0;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let position = Position {
+            line,
+            column: i - line_start + 1,
+        };
+        let start = i;
+
+        let kind = match bytes[i] {
+            b' ' | b'\t' | b'\r' => {
+                i += 1;
+                continue;
+            }
+            b'\n' => {
+                line += 1;
+                line_start = i + 1;
+                TokenKind::Newline
+            }
+            b'<' => TokenKind::LessThan,
+            b'>' => TokenKind::GreaterThan,
+            b':' => TokenKind::Colon,
+            b',' => TokenKind::Comma,
+            b'{' => TokenKind::OpenBrace,
+            b'}' => TokenKind::CloseBrace,
+            b'a'..=b'z' | b'A'..=b'Z' => {
+                while bytes.get(i + 1).is_some_and(u8::is_ascii_alphabetic) {
+                    i += 1;
+                }
+                TokenKind::Name(&source[start..=i])
+            }
+            b'0'..=b'9' => {
+                while bytes.get(i + 1).is_some_and(u8::is_ascii_digit) {
+                    i += 1;
+                }
+                let number = source[start..=i].parse().map_err(|_| CompileError {
+                    position,
+                    kind: ErrorKind::NumberTooLarge,
+                })?;
+                TokenKind::Number(number)
+            }
+            _ => {
+                return Err(CompileError {
+                    position,
+                    kind: ErrorKind::UnexpectedChar(source[i..].chars().next().unwrap()),
+                })
+            }
+        };
+
+        tokens.push(Token { kind, position });
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<TokenKind<'a>> {
+        self.tokens.get(self.index).map(|t| t.kind)
+    }
+
+    fn position(&self) -> Position {
+        self.tokens.get(self.index).map_or(self.end, |t| t.position)
+    }
+
+    fn unexpected(&self, expected: &'static str) -> CompileError {
+        CompileError {
+            position: self.position(),
+            kind: ErrorKind::UnexpectedToken {
+                expected,
+                found: self
+                    .peek()
+                    .map_or_else(|| "end of input".to_string(), |t| t.to_string()),
+            },
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &'static str) -> Result<(), CompileError> {
+        if self.peek() != Some(kind) {
+            return Err(self.unexpected(expected));
+        }
+        self.index += 1;
+        Ok(())
+    }
+
+    fn name(&mut self, expected: &'static str) -> Result<&'a str, CompileError> {
+        let Some(TokenKind::Name(name)) = self.peek() else {
+            return Err(self.unexpected(expected));
+        };
+        self.index += 1;
+        Ok(name)
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.peek() == Some(TokenKind::Newline) {
+            self.index += 1;
+        }
+    }
+
+    /// Parses `name{rule,...,fallback}`.
+    fn workflow(&mut self) -> Result<(&'a str, Workflow<'a>), CompileError> {
+        let name = self.name("a workflow name")?;
+        self.expect(TokenKind::OpenBrace, "'{'")?;
+
+        let mut rules = vec![];
+        loop {
+            let position = self.position();
+            let word = self.name("a rule or a target")?;
+
+            let is_less_than = match self.peek() {
+                Some(TokenKind::LessThan) => true,
+                Some(TokenKind::GreaterThan) => false,
+                _ => {
+                    self.expect(TokenKind::CloseBrace, "'<', '>' or '}'")?;
+                    let fallback = parse_output(word);
+                    return Ok((name, Workflow { rules, fallback }));
+                }
+            };
+            self.index += 1;
+
+            let input = match word {
+                "x" => Input::X,
+                "m" => Input::M,
+                "a" => Input::A,
+                "s" => Input::S,
+                _ => {
+                    return Err(CompileError {
+                        position,
+                        kind: ErrorKind::UnknownCategory(word.to_string()),
+                    })
+                }
+            };
+            let Some(TokenKind::Number(operand)) = self.peek() else {
+                return Err(self.unexpected("a number"));
+            };
+            self.index += 1;
+            self.expect(TokenKind::Colon, "':'")?;
+            let output = parse_output(self.name("a target")?);
+            self.expect(TokenKind::Comma, "','")?;
+
+            rules.push(Rule {
+                input,
+                is_less_than,
+                operand,
+                output,
+            });
+        }
+    }
+}
+
+fn parse_output(name: &str) -> Output<'_> {
+    match name {
+        "A" => Output::Accepted,
+        "R" => Output::Rejected,
+        name => Output::Rule(name),
+    }
+}
+
+impl<'a> Program<'a> {
+    /// Parses the workflow section of the input, one workflow per line.
+    pub fn parse(source: &'a str) -> Result<Self, CompileError> {
+        let end = Position {
+            line: source.matches('\n').count() + 1,
+            column: source.len() - source.rfind('\n').map_or(0, |i| i + 1) + 1,
+        };
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            index: 0,
+            end,
+        };
+
+        let mut program = Program {
+            order: vec![],
+            workflows: AHashMap::new(),
+        };
+        loop {
+            parser.skip_newlines();
+            if parser.peek().is_none() {
+                break;
+            }
+
+            let position = parser.position();
+            let (name, workflow) = parser.workflow()?;
+            if program.workflows.insert(name, workflow).is_some() {
+                return Err(CompileError {
+                    position,
+                    kind: ErrorKind::DuplicateWorkflow(name.to_string()),
+                });
+            }
+            program.order.push(name);
+
+            if !matches!(parser.peek(), None | Some(TokenKind::Newline)) {
+                return Err(parser.unexpected("a newline"));
+            }
+        }
+
+        Ok(program)
+    }
+
+    /// Runs a part with the given `[x, m, a, s]` ratings through the workflows, starting at
+    /// [`ENTRY`].
+    pub fn accepts(&self, part: [u16; 4]) -> bool {
+        let mut name = ENTRY;
+        loop {
+            match self.workflows[name].run(part) {
+                Output::Accepted => return true,
+                Output::Rejected => return false,
+                Output::Rule(next) => name = next,
+            }
+        }
+    }
+
+    /// Looks for everything that could go wrong when running the program on parts within
+    /// `bounds`.
+    pub fn validate(&self, bounds: &Ratings) -> Vec<Diagnostic<'a>> {
+        let mut diagnostics = vec![];
+        if !self.workflows.contains_key(ENTRY) {
+            diagnostics.push(Diagnostic::MissingEntry);
+        }
+
+        for &workflow in &self.order {
+            for output in self.workflows[workflow].outputs() {
+                if let Output::Rule(target) = output {
+                    if !self.workflows.contains_key(target) {
+                        diagnostics.push(Diagnostic::UndefinedTarget { workflow, target });
+                    }
+                }
+            }
+
+            let (unreachable, _) = unreachable_rules(&self.workflows[workflow], bounds);
+            diagnostics.extend(
+                unreachable
+                    .into_iter()
+                    .map(|rule| Diagnostic::UnreachableRule { workflow, rule }),
+            );
+        }
+
+        diagnostics.extend(self.cycles().into_iter().map(Diagnostic::Cycle));
+
+        if self.workflows.contains_key(ENTRY) {
+            let reachable = self.reachable();
+            diagnostics.extend(
+                self.order
+                    .iter()
+                    .filter(|name| !reachable.contains(*name))
+                    .map(|name| Diagnostic::UnreachableWorkflow(name)),
+            );
+        }
+
+        diagnostics
+    }
+
+    /// Returns the defined workflows that a workflow sends parts to.
+    fn targets(&self, name: &str) -> impl Iterator<Item = &'a str> + '_ {
+        self.workflows[name]
+            .outputs()
+            .filter_map(|output| match output {
+                Output::Rule(target) if self.workflows.contains_key(target) => Some(target),
+                _ => None,
+            })
+    }
+
+    fn reachable(&self) -> AHashSet<&'a str> {
+        let mut reachable = AHashSet::from([ENTRY]);
+        let mut stack = vec![ENTRY];
+        while let Some(name) = stack.pop() {
+            for target in self.targets(name) {
+                if reachable.insert(target) {
+                    stack.push(target);
+                }
+            }
+        }
+        reachable
+    }
+
+    fn cycles(&self) -> Vec<Vec<&'a str>> {
+        let mut cycles = vec![];
+        let mut done = AHashSet::new();
+        for &name in &self.order {
+            self.find_cycles(name, &mut vec![], &mut done, &mut cycles);
+        }
+        cycles
+    }
+
+    fn find_cycles(
+        &self,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        done: &mut AHashSet<&'a str>,
+        cycles: &mut Vec<Vec<&'a str>>,
+    ) {
+        if let Some(start) = path.iter().position(|n| *n == name) {
+            let cycle = path[start..].to_vec();
+            if !cycles.contains(&cycle) {
+                cycles.push(cycle);
+            }
+            return;
+        }
+        if done.contains(name) {
+            return;
+        }
+
+        path.push(name);
+        for target in self.targets(name).collect::<Vec<_>>() {
+            self.find_cycles(target, path, done, cycles);
+        }
+        path.pop();
+        done.insert(name);
+    }
+
+    /// Simplifies the program without changing which parts within `bounds` it accepts, until
+    /// nothing changes. Parts outside the bounds may be accepted differently afterwards.
+    ///
+    /// - rules that can never match are removed, and a rule that always matches becomes the
+    ///   fallback
+    /// - rules at the end that go to the same place as the fallback are removed
+    /// - workflows without rules are replaced by their fallback wherever they're used
+    /// - workflows that are only used as the fallback of one other workflow are inlined there
+    /// - workflows that can't be reached from [`ENTRY`] are removed
+    pub fn optimize(&mut self, bounds: &Ratings) {
+        while self.fold_rules(bounds) | self.resolve_aliases() | self.inline_single_use() {}
+
+        if self.workflows.contains_key(ENTRY) {
+            let reachable = self.reachable();
+            self.order.retain(|name| reachable.contains(name));
+            self.workflows.retain(|name, _| reachable.contains(name));
+        }
+    }
+
+    fn fold_rules(&mut self, bounds: &Ratings) -> bool {
+        let mut changed = false;
+        for workflow in self.workflows.values_mut() {
+            let (unreachable, always_matches) = unreachable_rules(workflow, bounds);
+            let before = workflow.clone();
+
+            if let Some(rule) = always_matches {
+                workflow.fallback = workflow.rules[rule].output;
+                workflow.rules.truncate(rule);
+            }
+            let mut index = 0;
+            workflow.rules.retain(|_| {
+                index += 1;
+                !unreachable.contains(&(index - 1))
+            });
+            while workflow
+                .rules
+                .last()
+                .is_some_and(|r| r.output == workflow.fallback)
+            {
+                workflow.rules.pop();
+            }
+
+            changed |= *workflow != before;
+        }
+        changed
+    }
+
+    fn resolve_aliases(&mut self) -> bool {
+        let aliases = self
+            .workflows
+            .iter()
+            .filter(|(name, workflow)| {
+                **name != ENTRY
+                    && workflow.rules.is_empty()
+                    && workflow.fallback != Output::Rule(name)
+            })
+            .map(|(name, workflow)| (*name, workflow.fallback))
+            .collect::<AHashMap<_, _>>();
+        // aliases can point to other aliases, so follow them all the way, stopping at cycles
+        let resolve = |mut output: Output<'a>| {
+            for _ in 0..=aliases.len() {
+                match output {
+                    Output::Rule(name) if aliases.contains_key(name) => output = aliases[name],
+                    _ => break,
+                }
+            }
+            output
+        };
+
+        let mut changed = false;
+        for workflow in self.workflows.values_mut() {
+            let before = workflow.clone();
+            for rule in &mut workflow.rules {
+                rule.output = resolve(rule.output);
+            }
+            workflow.fallback = resolve(workflow.fallback);
+            changed |= *workflow != before;
+        }
+
+        let count = self.workflows.len();
+        self.remove_unused(|name| aliases.contains_key(name));
+        changed || self.workflows.len() != count
+    }
+
+    fn inline_single_use(&mut self) -> bool {
+        let mut uses = AHashMap::<&str, Vec<(&str, bool)>>::new();
+        for (&name, workflow) in &self.workflows {
+            for (i, output) in workflow.outputs().enumerate() {
+                if let Output::Rule(target) = output {
+                    uses.entry(target)
+                        .or_default()
+                        .push((name, i == workflow.rules.len()));
+                }
+            }
+        }
+
+        let single_use = |&target| match uses.get(target)?[..] {
+            [(user, true)] if target != ENTRY && user != target => Some((target, user)),
+            _ => None,
+        };
+        let Some((target, user)) = self.order.iter().find_map(single_use) else {
+            return false;
+        };
+
+        let inlined = self.workflows.remove(target).unwrap();
+        let workflow = self.workflows.get_mut(user).unwrap();
+        workflow.rules.extend(inlined.rules);
+        workflow.fallback = inlined.fallback;
+        self.order.retain(|name| *name != target);
+        true
+    }
+
+    /// Removes workflows that are no longer used by any other workflow.
+    fn remove_unused(&mut self, candidate: impl Fn(&str) -> bool) {
+        let used = self
+            .workflows
+            .values()
+            .flat_map(Workflow::outputs)
+            .filter_map(|output| match output {
+                Output::Rule(name) => Some(name),
+                _ => None,
+            })
+            .collect::<AHashSet<_>>();
+        let unused = |name: &str| candidate(name) && !used.contains(name);
+
+        self.order.retain(|name| !unused(name));
+        self.workflows.retain(|name, _| !unused(name));
+    }
+}
+
+impl Display for TokenKind<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenKind::Name(name) => write!(f, "'{name}'"),
+            TokenKind::Number(number) => write!(f, "{number}"),
+            TokenKind::LessThan => f.write_str("'<'"),
+            TokenKind::GreaterThan => f.write_str("'>'"),
+            TokenKind::Colon => f.write_str("':'"),
+            TokenKind::Comma => f.write_str("','"),
+            TokenKind::OpenBrace => f.write_str("'{'"),
+            TokenKind::CloseBrace => f.write_str("'}'"),
+            TokenKind::Newline => f.write_str("a newline"),
+        }
+    }
+}
+
+impl Display for CompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: ", self.position.line, self.position.column)?;
+        match &self.kind {
+            ErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {c:?}"),
+            ErrorKind::NumberTooLarge => write!(f, "number is too large"),
+            ErrorKind::UnexpectedToken { expected, found } => {
+                write!(f, "expected {expected}, found {found}")
+            }
+            ErrorKind::UnknownCategory(category) => {
+                write!(f, "unknown category '{category}', expected x, m, a or s")
+            }
+            ErrorKind::DuplicateWorkflow(name) => write!(f, "workflow '{name}' is already defined"),
+        }
+    }
+}
+
+impl Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Diagnostic::MissingEntry => write!(f, "there is no '{ENTRY}' workflow"),
+            Diagnostic::UndefinedTarget { workflow, target } => {
+                write!(
+                    f,
+                    "'{workflow}' sends parts to '{target}', which isn't defined"
+                )
+            }
+            Diagnostic::Cycle(cycle) => {
+                write!(
+                    f,
+                    "parts can loop forever: {} -> {}",
+                    cycle.join(" -> "),
+                    cycle[0]
+                )
+            }
+            Diagnostic::UnreachableRule { workflow, rule } => {
+                write!(f, "rule {} of '{workflow}' can never match", rule + 1)
+            }
+            Diagnostic::UnreachableWorkflow(name) => {
+                write!(f, "'{name}' can't be reached from '{ENTRY}'")
+            }
+        }
+    }
+}
+
+/// Writes the program back in the puzzle syntax.
+impl Display for Program<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for name in &self.order {
+            writeln!(f, "{name}{}", self.workflows[name])?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the indices of the rules that can never match, with the amount of rules for the
+/// fallback, and the first rule that always matches if there is one, for parts within `bounds`.
+fn unreachable_rules(workflow: &Workflow, bounds: &Ratings) -> (Vec<usize>, Option<usize>) {
+    let mut ranges = bounds.clone();
+    let mut unreachable = vec![];
+    let mut always_matches = None;
+
+    for (i, rule) in workflow.rules.iter().enumerate() {
+        let index = rule.input as usize;
+        let (matched, unmatched) = rule.split(&ranges[index]);
+        if matched.is_empty() || always_matches.is_some() {
+            unreachable.push(i);
+        } else if unmatched.is_empty() {
+            always_matches = Some(i);
+        }
+        ranges[index] = unmatched;
+    }
+    if always_matches.is_some() {
+        unreachable.push(workflow.rules.len());
+    }
+
+    (unreachable, always_matches)
+}
+
+#[cfg(test)]
+fn count_accepted(program: &Program) -> u128 {
+    program.accepted_regions([RATINGS; 4]).volume()
+}
+
+#[test]
+fn test_tokenize() {
+    use TokenKind::*;
+
+    let tokens = tokenize("px{a<2006:qkq,A}\n").unwrap();
+    assert_eq!(
+        vec![
+            Name("px"),
+            OpenBrace,
+            Name("a"),
+            LessThan,
+            Number(2006),
+            Colon,
+            Name("qkq"),
+            Comma,
+            Name("A"),
+            CloseBrace,
+            Newline,
+        ],
+        tokens.iter().map(|t| t.kind).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        Position {
+            line: 1,
+            column: 11
+        },
+        tokens[6].position
+    );
+
+    let error = tokenize("px{A}\nin{x=1:A,R}").unwrap_err();
+    assert_eq!("2:5: unexpected character '='", error.to_string());
+    let error = tokenize("in{x<70000:A,R}").unwrap_err();
+    assert_eq!("1:6: number is too large", error.to_string());
+}
+
+#[test]
+fn test_parse_errors() {
+    let error = |source| Program::parse(source).unwrap_err().to_string();
+
+    assert_eq!("1:6: expected a number, found 'b'", error("in{x<b:A,R}"));
+    assert_eq!(
+        "1:4: unknown category 'q', expected x, m, a or s",
+        error("in{q<10:A,R}")
+    );
+    assert_eq!("1:9: expected ',', found '}'", error("in{x<1:A}"));
+    assert_eq!(
+        "1:11: expected '<', '>' or '}', found ','",
+        error("in{x<1:A,R,A}")
+    );
+    assert_eq!(
+        "3:1: workflow 'in' is already defined",
+        error("in{A}\npx{R}\nin{R}")
+    );
+    assert_eq!("1:6: expected a newline, found 'px'", error("in{A}px{R}"));
+    assert_eq!(
+        "2:4: expected a rule or a target, found end of input",
+        error("in{A}\npx{")
+    );
+}
+
+#[test]
+fn test_pretty_print() {
+    let source = Day::INPUT_SAMPLE.split_once("\n\n").unwrap().0;
+    let program = Program::parse(source).unwrap();
+    assert_eq!(format!("{source}\n"), program.to_string());
+    assert_eq!(program, Program::parse(&program.to_string()).unwrap());
+    assert!(program.validate(&[RATINGS; 4]).is_empty());
+}
+
+#[test]
+fn test_validate() {
+    let program = Program::parse(
+        "\
+px{x<10:qq,x<5:A,m>4000:R,ab}
+ab{a>10:px,s<2:zz,R}
+in{R}
+lone{A}",
+    )
+    .unwrap();
+
+    let diagnostics = program.validate(&[RATINGS; 4]);
+    assert_eq!(
+        vec![
+            Diagnostic::UndefinedTarget {
+                workflow: "px",
+                target: "qq"
+            },
+            Diagnostic::UnreachableRule {
+                workflow: "px",
+                rule: 1
+            },
+            Diagnostic::UnreachableRule {
+                workflow: "px",
+                rule: 2
+            },
+            Diagnostic::UndefinedTarget {
+                workflow: "ab",
+                target: "zz"
+            },
+            Diagnostic::Cycle(vec!["px", "ab"]),
+            Diagnostic::UnreachableWorkflow("px"),
+            Diagnostic::UnreachableWorkflow("ab"),
+            Diagnostic::UnreachableWorkflow("lone"),
+        ],
+        diagnostics
+    );
+    assert_eq!(
+        "parts can loop forever: px -> ab -> px",
+        diagnostics[4].to_string()
+    );
+
+    let program = Program::parse("px{x>10:A,R}").unwrap();
+    assert_eq!(
+        vec![Diagnostic::MissingEntry],
+        program.validate(&[RATINGS; 4])
+    );
+
+    // aliases pointing at each other can't be resolved, but optimizing still finishes
+    let mut program = Program::parse("in{x>10:a,R}\na{b}\nb{a}").unwrap();
+    assert_eq!(
+        vec![Diagnostic::Cycle(vec!["a", "b"])],
+        program.validate(&[RATINGS; 4])
+    );
+    program.optimize(&[RATINGS; 4]);
+    assert_eq!("in{x>10:b,R}\nb{b}\n", program.to_string());
+}
+
+#[test]
+fn test_optimize() {
+    let source = Day::INPUT_SAMPLE.split_once("\n\n").unwrap().0;
+    let mut program = Program::parse(source).unwrap();
+    program.optimize(&[RATINGS; 4]);
+    assert_eq!(
+        "\
+px{a<2006:qkq,m>2090:A,s<537:R,x>2440:R,A}
+qkq{x<1416:A,x>2662:A,R}
+in{s<1351:px,s>2770:A,m<1801:hdj,R}
+hdj{m>838:A,a>1716:R,A}
+",
+        program.to_string()
+    );
+}
+
+#[test]
+fn test_optimize_bounds() {
+    // every part in the puzzle has x <= 4000, so the rule never matches
+    let source = "in{x>4000:R,A}";
+    let mut program = Program::parse(source).unwrap();
+    program.optimize(&[RATINGS; 4]);
+    assert_eq!("in{A}\n", program.to_string());
+    assert_eq!(
+        vec![Diagnostic::UnreachableRule {
+            workflow: "in",
+            rule: 0
+        }],
+        Program::parse(source).unwrap().validate(&[RATINGS; 4])
+    );
+
+    // but it does for larger ratings
+    let bounds = [1..=5000, 1..=1, 1..=1, 1..=1];
+    let mut program = Program::parse(source).unwrap();
+    assert!(program.validate(&bounds).is_empty());
+    program.optimize(&bounds);
+    assert_eq!("in{x>4000:R,A}\n", program.to_string());
+    assert_eq!(4000, program.accepted_regions(bounds).volume());
+    assert!(!program.accepts([4500, 1, 1, 1]));
+}
+
+#[test]
+fn test_optimize_keeps_results() {
+    for input in [Day::INPUT_SAMPLE, Day::INPUT_REAL] {
+        let (source, ratings) = input.split_once("\n\n").unwrap();
+        let program = Program::parse(source).unwrap();
+        let mut optimized = program.clone();
+        optimized.optimize(&[RATINGS; 4]);
+
+        assert!(optimized.validate(&[RATINGS; 4]).is_empty());
+        assert!(optimized.workflows.len() < program.workflows.len());
+        assert_eq!(count_accepted(&program), count_accepted(&optimized));
+        for part in super::parse_ratings(ratings) {
+            assert_eq!(program.accepts(part), optimized.accepts(part));
+        }
+
+        // optimizing again doesn't change anything
+        let mut again = optimized.clone();
+        again.optimize(&[RATINGS; 4]);
+        assert_eq!(optimized, again);
+    }
+}
@@ -0,0 +1,213 @@
+//! Exports the workflows as a Graphviz or Mermaid flowchart.
+
+use std::fmt::Write;
+
+use ahash::AHashMap;
+
+use super::compiler::{Program, ENTRY};
+use super::regions::AcceptedRegions;
+use super::Output;
+
+#[cfg(test)]
+use {super::Day, crate::SolutionSilver};
+
+/// An edge from a workflow to where one of its rules (or its fallback) sends parts.
+struct Edge<'a> {
+    from: &'a str,
+    to: Output<'a>,
+    /// The condition of the rule, `None` for the fallback.
+    condition: Option<String>,
+    /// How thick to draw the edge, between 1 and `MAX_WIDTH`.
+    width: f64,
+}
+
+const MAX_WIDTH: f64 = 8.0;
+
+impl<'a> Program<'a> {
+    /// Returns every edge in the program, in the order the workflows were defined. When `flow` is
+    /// given, edges get thicker with the amount of accepted parts going through them.
+    fn edges(&self, flow: Option<&AcceptedRegions>) -> Vec<Edge<'a>> {
+        // (workflow, rule) -> amount of accepted parts
+        let mut volumes = AHashMap::<(&str, usize), u128>::new();
+        for region in flow.iter().flat_map(|f| &f.regions) {
+            for (workflow, rule) in region.path.iter().zip(&region.rules) {
+                *volumes.entry((workflow, *rule)).or_default() += region.volume();
+            }
+        }
+        let max_volume = volumes.values().copied().max().unwrap_or(0).max(1);
+        let width = |workflow, rule| {
+            let volume = volumes.get(&(workflow, rule)).copied().unwrap_or(0);
+            1.0 + (MAX_WIDTH - 1.0) * volume as f64 / max_volume as f64
+        };
+
+        let mut edges = vec![];
+        for &name in &self.order {
+            let workflow = &self.workflows[name];
+            for (i, rule) in workflow.rules().iter().enumerate() {
+                let condition = format!("{}{}{}", rule.input(), rule.operator(), rule.operand());
+                edges.push(Edge {
+                    from: name,
+                    to: rule.output(),
+                    condition: Some(condition),
+                    width: width(name, i),
+                });
+            }
+            edges.push(Edge {
+                from: name,
+                to: workflow.fallback(),
+                condition: None,
+                width: width(name, workflow.rules().len()),
+            });
+        }
+        edges
+    }
+
+    /// Exports the workflows as a Graphviz graph. Each rule is an edge labelled with its
+    /// condition, and the fallback is an edge labelled `else`.
+    pub fn to_dot(&self, flow: Option<&AcceptedRegions>) -> String {
+        let mut dot = String::new();
+        _ = writeln!(dot, "digraph workflows {{");
+        _ = writeln!(dot, "    node [shape=box];");
+        _ = writeln!(
+            dot,
+            "    \"A\" [shape=doublecircle, style=filled, fillcolor=palegreen];"
+        );
+        _ = writeln!(
+            dot,
+            "    \"R\" [shape=doublecircle, style=filled, fillcolor=lightcoral];"
+        );
+        for name in &self.order {
+            let style = if *name == ENTRY { ", style=bold" } else { "" };
+            _ = writeln!(dot, "    \"{name}\" [label=\"{name}\"{style}];");
+        }
+
+        for edge in self.edges(flow) {
+            let label = edge.condition.as_deref().unwrap_or("else");
+            let style = if flow.is_some() {
+                format!(", penwidth={:.2}", edge.width)
+            } else {
+                String::new()
+            };
+            _ = writeln!(
+                dot,
+                "    \"{}\" -> \"{}\" [label=\"{label}\"{style}];",
+                edge.from, edge.to,
+            );
+        }
+        _ = writeln!(dot, "}}");
+
+        dot
+    }
+
+    /// Exports the workflows as a Mermaid flowchart, with the same edges as [`Self::to_dot`].
+    pub fn to_mermaid(&self, flow: Option<&AcceptedRegions>) -> String {
+        // workflow names like `end` are keywords in Mermaid, so every workflow id gets a prefix
+        let id = |output: Output| match output {
+            Output::Accepted => "A".to_string(),
+            Output::Rejected => "R".to_string(),
+            Output::Rule(name) => format!("w_{name}"),
+        };
+
+        let mut mermaid = String::new();
+        _ = writeln!(mermaid, "flowchart LR");
+        _ = writeln!(mermaid, "    A(((A)))");
+        _ = writeln!(mermaid, "    R(((R)))");
+        for name in &self.order {
+            _ = writeln!(mermaid, "    {}[\"{name}\"]", id(Output::Rule(name)));
+        }
+
+        let edges = self.edges(flow);
+        for edge in &edges {
+            let label = edge
+                .condition
+                .as_deref()
+                .unwrap_or("else")
+                .replace('<', "#lt;")
+                .replace('>', "#gt;");
+            _ = writeln!(
+                mermaid,
+                "    {} -->|\"{label}\"| {}",
+                id(Output::Rule(edge.from)),
+                id(edge.to),
+            );
+        }
+        if flow.is_some() {
+            for (i, edge) in edges.iter().enumerate() {
+                _ = writeln!(
+                    mermaid,
+                    "    linkStyle {i} stroke-width:{:.2}px",
+                    edge.width
+                );
+            }
+        }
+        _ = writeln!(mermaid, "    style A fill:palegreen");
+        _ = writeln!(mermaid, "    style R fill:lightcoral");
+
+        mermaid
+    }
+}
+
+#[test]
+fn test_to_dot() {
+    let program = Program::parse("in{a<2006:qkq,m>2090:A,R}\nqkq{x<1416:A,R}").unwrap();
+    assert_eq!(
+        "\
+digraph workflows {
+    node [shape=box];
+    \"A\" [shape=doublecircle, style=filled, fillcolor=palegreen];
+    \"R\" [shape=doublecircle, style=filled, fillcolor=lightcoral];
+    \"in\" [label=\"in\", style=bold];
+    \"qkq\" [label=\"qkq\"];
+    \"in\" -> \"qkq\" [label=\"a<2006\"];
+    \"in\" -> \"A\" [label=\"m>2090\"];
+    \"in\" -> \"R\" [label=\"else\"];
+    \"qkq\" -> \"A\" [label=\"x<1416\"];
+    \"qkq\" -> \"R\" [label=\"else\"];
+}
+",
+        program.to_dot(None)
+    );
+
+    // x<1416 lets through 1415 * 2005 * 4000 * 4000 parts, m>2090 lets through
+    // 1910 * 1995 * 4000 * 4000, which is the most
+    let flow = program.accepted_regions([1..=4000, 1..=4000, 1..=4000, 1..=4000]);
+    let dot = program.to_dot(Some(&flow));
+    assert!(dot.contains("\"in\" -> \"A\" [label=\"m>2090\", penwidth=8.00];"));
+    assert!(dot.contains("\"in\" -> \"R\" [label=\"else\", penwidth=1.00];"));
+    let width = 1.0 + 7.0 * (1415.0 * 2005.0) / (1910.0 * 1995.0);
+    assert!(dot.contains(&format!(
+        "\"qkq\" -> \"A\" [label=\"x<1416\", penwidth={width:.2}];"
+    )));
+    assert!(dot.contains(&format!(
+        "\"in\" -> \"qkq\" [label=\"a<2006\", penwidth={width:.2}];"
+    )));
+}
+
+#[test]
+fn test_to_mermaid() {
+    let program = Program::parse("in{s>10:end,R}\nend{x<5:A,R}").unwrap();
+    assert_eq!(
+        "\
+flowchart LR
+    A(((A)))
+    R(((R)))
+    w_in[\"in\"]
+    w_end[\"end\"]
+    w_in -->|\"s#gt;10\"| w_end
+    w_in -->|\"else\"| R
+    w_end -->|\"x#lt;5\"| A
+    w_end -->|\"else\"| R
+    style A fill:palegreen
+    style R fill:lightcoral
+",
+        program.to_mermaid(None)
+    );
+
+    let source = Day::INPUT_SAMPLE.split_once("\n\n").unwrap().0;
+    let program = Program::parse(source).unwrap();
+    let flow = program.accepted_regions([1..=4000, 1..=4000, 1..=4000, 1..=4000]);
+    let mermaid = program.to_mermaid(Some(&flow));
+    let edges = mermaid.matches(" -->|").count();
+    assert_eq!(edges, mermaid.matches("linkStyle").count());
+    assert_eq!(1, mermaid.matches("stroke-width:8.00px").count());
+}
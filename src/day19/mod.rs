@@ -1,11 +1,15 @@
 use std::ops::RangeInclusive;
 
-use ahash::AHashMap;
-
 use crate::utils::fast_parse_int;
 
 use super::*;
 
+pub mod compiler;
+pub mod graph;
+pub mod regions;
+
+use compiler::{Program, RATINGS};
+
 pub struct Day;
 
 impl SolutionSilver<usize> for Day {
@@ -15,207 +19,48 @@ impl SolutionSilver<usize> for Day {
 
     fn calculate_silver(input: &str) -> usize {
         let (workflows, ratings) = input.split_once("\n\n").unwrap();
+        let program = Program::parse(workflows).unwrap();
 
-        let workflows = workflows
-            .split('\n')
-            .map(|l| {
-                let (name, rest) = l.split_once('{').unwrap();
-                let (rules, fallback_rule) = rest.rsplit_once(',').unwrap();
-                let rules = rules
-                    .split(',')
-                    .map(|r| {
-                        let (operand, output) = r[2..].split_once(':').unwrap();
-
-                        Rule {
-                            input: match &r.as_bytes()[0] {
-                                b'x' => Input::X,
-                                b'm' => Input::M,
-                                b'a' => Input::A,
-                                b's' => Input::S,
-                                _ => panic!(),
-                            },
-                            is_less_than: r.as_bytes()[1] == b'<',
-                            operand: fast_parse_int(operand) as u16,
-                            output: match output {
-                                "A" => Output::Accepted,
-                                "R" => Output::Rejected,
-                                r => Output::Rule(r),
-                            },
-                        }
-                    })
-                    .collect::<Vec<_>>();
-
-                let fallback = match fallback_rule.trim_end_matches('}') {
-                    "A" => Output::Accepted,
-                    "R" => Output::Rejected,
-                    r => Output::Rule(r),
-                };
-
-                (name, Workflow { rules, fallback })
-            })
-            .collect::<AHashMap<_, _>>();
-
-        ratings
-            .split('\n')
-            .map(|r| {
-                let mut inputs = r[1..r.len() - 1]
-                    .split(',')
-                    .map(|s| s.split_once('=').unwrap().1)
-                    .map(fast_parse_int);
-
-                (
-                    inputs.next().unwrap() as u16,
-                    inputs.next().unwrap() as u16,
-                    inputs.next().unwrap() as u16,
-                    inputs.next().unwrap() as u16,
-                )
-            })
-            .filter(|input| {
-                let mut workflow_name = "in";
-                loop {
-                    let workflow = &workflows[workflow_name];
-
-                    let next_rule = workflow
-                        .rules
-                        .iter()
-                        .find_map(|r| {
-                            let input = match r.input {
-                                Input::X => input.0,
-                                Input::M => input.1,
-                                Input::A => input.2,
-                                Input::S => input.3,
-                            };
-
-                            if (r.is_less_than && input < r.operand)
-                                || (!r.is_less_than && input > r.operand)
-                            {
-                                Some(&r.output)
-                            } else {
-                                None
-                            }
-                        })
-                        .unwrap_or(&workflow.fallback);
-
-                    match next_rule {
-                        Output::Accepted => return true,
-                        Output::Rejected => return false,
-                        Output::Rule(r) => {
-                            workflow_name = r;
-                        }
-                    }
-                }
-            })
-            .map(|i| (i.0 + i.1 + i.2 + i.3) as usize)
+        parse_ratings(ratings)
+            .filter(|part| program.accepts(*part))
+            .map(|part| part.iter().map(|r| *r as usize).sum::<usize>())
             .sum()
     }
 }
 
 impl SolutionGold<usize, usize> for Day {
     fn calculate_gold(input: &str) -> usize {
-        let workflows = input
-            .split_once("\n\n")
-            .unwrap()
-            .0
-            .split('\n')
-            .map(|l| {
-                let (name, rest) = l.split_once('{').unwrap();
-                let (rules, fallback_rule) = rest.rsplit_once(',').unwrap();
-                let rules = rules
-                    .split(',')
-                    .map(|r| {
-                        let (operand, output) = r[2..].split_once(':').unwrap();
-
-                        Rule {
-                            input: match &r.as_bytes()[0] {
-                                b'x' => Input::X,
-                                b'm' => Input::M,
-                                b'a' => Input::A,
-                                b's' => Input::S,
-                                _ => panic!(),
-                            },
-                            is_less_than: r.as_bytes()[1] == b'<',
-                            operand: fast_parse_int(operand) as u16,
-                            output: match output {
-                                "A" => Output::Accepted,
-                                "R" => Output::Rejected,
-                                r => Output::Rule(r),
-                            },
-                        }
-                    })
-                    .collect::<Vec<_>>();
-
-                let fallback = match fallback_rule.trim_end_matches('}') {
-                    "A" => Output::Accepted,
-                    "R" => Output::Rejected,
-                    r => Output::Rule(r),
-                };
-
-                (name, Workflow { rules, fallback })
-            })
-            .collect::<AHashMap<_, _>>();
-
-        let range = [1..=4000, 1..=4000, 1..=4000, 1..=4000];
-
-        do_gold_math(&workflows, "in", range)
+        let workflows = input.split_once("\n\n").unwrap().0;
+        let program = Program::parse(workflows).unwrap();
+
+        program.accepted_regions([RATINGS; 4]).volume() as usize
     }
 }
 
-fn do_gold_math(
-    workflows: &AHashMap<&str, Workflow>,
-    start: &str,
-    ranges: [RangeInclusive<u16>; 4],
-) -> usize {
-    let workflow = &workflows[start];
-
-    let (acc, ranges) = workflow
-        .rules
-        .iter()
-        .fold((0usize, ranges), |(acc, mut ranges), rule| {
-            let index = match rule.input {
-                Input::X => 0,
-                Input::M => 1,
-                Input::A => 2,
-                Input::S => 3,
-            };
-            let (true_range, false_range) = if rule.is_less_than {
-                (
-                    *ranges[index].start()..=(rule.operand - 1),
-                    rule.operand..=*ranges[index].end(),
-                )
-            } else {
-                (
-                    (rule.operand + 1)..=*ranges[index].end(),
-                    *ranges[index].start()..=rule.operand,
-                )
-            };
-
-            let mut r2 = ranges.clone();
-            r2[index] = true_range.clone();
-
-            let new_acc = match &rule.output {
-                Output::Accepted => r2[0].len() * r2[1].len() * r2[2].len() * r2[3].len(),
-                Output::Rejected => 0,
-                Output::Rule(r_output) => do_gold_math(workflows, r_output, r2),
-            };
-
-            ranges[index] = false_range;
-            (acc + new_acc, ranges)
-        });
-
-    acc + match &workflow.fallback {
-        Output::Accepted => ranges[0].len() * ranges[1].len() * ranges[2].len() * ranges[3].len(),
-        Output::Rejected => 0,
-        Output::Rule(r_output) => do_gold_math(workflows, r_output, ranges),
-    }
+/// Parses the ratings of each part, as `[x, m, a, s]`.
+pub fn parse_ratings(ratings: &str) -> impl Iterator<Item = [u16; 4]> + '_ {
+    ratings.lines().map(|r| {
+        let mut inputs = r[1..r.len() - 1]
+            .split(',')
+            .map(|s| s.split_once('=').unwrap().1)
+            .map(|s| fast_parse_int(s) as u16);
+
+        [
+            inputs.next().unwrap(),
+            inputs.next().unwrap(),
+            inputs.next().unwrap(),
+            inputs.next().unwrap(),
+        ]
+    })
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Workflow<'a> {
     rules: Vec<Rule<'a>>,
     fallback: Output<'a>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Rule<'a> {
     input: Input,
     is_less_than: bool,
@@ -223,7 +68,7 @@ pub struct Rule<'a> {
     output: Output<'a>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Input {
     X,
     M,
@@ -231,13 +76,140 @@ pub enum Input {
     S,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Output<'a> {
     Accepted,
     Rejected,
     Rule(&'a str),
 }
 
+impl<'a> Workflow<'a> {
+    pub fn rules(&self) -> &[Rule<'a>] {
+        &self.rules
+    }
+
+    pub fn fallback(&self) -> Output<'a> {
+        self.fallback
+    }
+
+    /// Returns where a part with the given `[x, m, a, s]` ratings goes next.
+    pub fn run(&self, part: [u16; 4]) -> Output<'a> {
+        self.rules
+            .iter()
+            .find(|r| r.matches(part[r.input as usize]))
+            .map_or(self.fallback, |r| r.output)
+    }
+
+    /// Returns the outputs of all rules, followed by the fallback.
+    pub fn outputs(&self) -> impl Iterator<Item = Output<'a>> + '_ {
+        self.rules
+            .iter()
+            .map(|r| r.output)
+            .chain(std::iter::once(self.fallback))
+    }
+}
+
+impl<'a> Rule<'a> {
+    pub fn input(&self) -> Input {
+        self.input
+    }
+
+    pub fn output(&self) -> Output<'a> {
+        self.output
+    }
+
+    pub fn operator(&self) -> char {
+        if self.is_less_than {
+            '<'
+        } else {
+            '>'
+        }
+    }
+
+    pub fn operand(&self) -> u16 {
+        self.operand
+    }
+
+    pub fn matches(&self, rating: u16) -> bool {
+        if self.is_less_than {
+            rating < self.operand
+        } else {
+            rating > self.operand
+        }
+    }
+
+    /// Splits a range of ratings into the ratings this rule matches and the ones it doesn't.
+    /// Either may be empty, in which case its start is after its end.
+    pub fn split(&self, range: &RangeInclusive<u16>) -> (RangeInclusive<u16>, RangeInclusive<u16>) {
+        let (start, end) = (*range.start(), *range.end());
+        match (self.is_less_than, self.operand) {
+            (true, 0) | (false, u16::MAX) => (RangeInclusive::new(1, 0), start..=end),
+            (true, operand) => (start..=end.min(operand - 1), start.max(operand)..=end),
+            (false, operand) => (start.max(operand + 1)..=end, start..=end.min(operand)),
+        }
+    }
+}
+
+impl Display for Input {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Input::X => "x",
+            Input::M => "m",
+            Input::A => "a",
+            Input::S => "s",
+        })
+    }
+}
+
+impl Display for Output<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Output::Accepted => f.write_str("A"),
+            Output::Rejected => f.write_str("R"),
+            Output::Rule(name) => f.write_str(name),
+        }
+    }
+}
+
+impl Display for Rule<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let operator = self.operator();
+        write!(
+            f,
+            "{}{operator}{}:{}",
+            self.input, self.operand, self.output
+        )
+    }
+}
+
+impl Display for Workflow<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("{")?;
+        for rule in &self.rules {
+            write!(f, "{rule},")?;
+        }
+        write!(f, "{}}}", self.fallback)
+    }
+}
+
+#[test]
+fn test_rule_split() {
+    let rule = |is_less_than, operand| Rule {
+        input: Input::X,
+        is_less_than,
+        operand,
+        output: Output::Accepted,
+    };
+
+    assert_eq!((1..=9, 10..=4000), rule(true, 10).split(&(1..=4000)));
+    assert_eq!((11..=4000, 1..=10), rule(false, 10).split(&(1..=4000)));
+    let (matched, unmatched) = rule(true, 10).split(&(20..=30));
+    assert!(matched.is_empty());
+    assert_eq!(20..=30, unmatched);
+    assert!(rule(true, 0).split(&(0..=30)).0.is_empty());
+    assert!(rule(false, u16::MAX).split(&(0..=u16::MAX)).0.is_empty());
+}
+
 #[test]
 fn test_silver_sample() {
     let output = Day::calculate_silver(Day::INPUT_SAMPLE);
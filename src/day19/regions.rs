@@ -0,0 +1,343 @@
+//! Symbolic analysis of which parts a program accepts, as boxes of ratings.
+
+use std::ops::RangeInclusive;
+
+use super::compiler::{Program, ENTRY};
+use super::Output;
+
+#[cfg(test)]
+use {super::Day, crate::SolutionSilver};
+
+/// An inclusive range for each of the `[x, m, a, s]` ratings.
+pub type Ratings = [RangeInclusive<u16>; 4];
+
+/// A box of parts that all take the same path through the workflows and get accepted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region<'a> {
+    pub ratings: Ratings,
+    /// The workflows the parts go through, starting at [`ENTRY`].
+    pub path: Vec<&'a str>,
+    /// The index of the rule that sent the parts on from each workflow in `path`. The amount of
+    /// rules in the workflow stands for the fallback.
+    pub rules: Vec<usize>,
+}
+
+/// Every part within `bounds` that a program accepts, split into disjoint regions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AcceptedRegions<'a> {
+    pub bounds: Ratings,
+    pub regions: Vec<Region<'a>>,
+}
+
+impl Region<'_> {
+    pub fn volume(&self) -> u128 {
+        volume(&self.ratings)
+    }
+
+    pub fn contains(&self, part: [u16; 4]) -> bool {
+        self.ratings.iter().zip(part).all(|(r, p)| r.contains(&p))
+    }
+}
+
+impl<'a> Program<'a> {
+    /// Finds every part within `bounds` that gets accepted, by splitting the bounds at each rule.
+    ///
+    /// Panics if parts can go around in a circle, see [`Program::validate`].
+    pub fn accepted_regions(&self, bounds: Ratings) -> AcceptedRegions<'a> {
+        let mut regions = vec![];
+        self.split_regions(
+            ENTRY,
+            bounds.clone(),
+            &mut vec![],
+            &mut vec![],
+            &mut regions,
+        );
+        AcceptedRegions { bounds, regions }
+    }
+
+    fn split_regions(
+        &self,
+        name: &'a str,
+        mut ratings: Ratings,
+        path: &mut Vec<&'a str>,
+        rules: &mut Vec<usize>,
+        regions: &mut Vec<Region<'a>>,
+    ) {
+        assert!(!path.contains(&name), "workflows loop at {name}");
+        path.push(name);
+
+        let workflow = &self.workflows[name];
+        let mut next = vec![];
+        for (i, rule) in workflow.rules().iter().enumerate() {
+            let index = rule.input() as usize;
+            let (matched, unmatched) = rule.split(&ratings[index]);
+            if !matched.is_empty() {
+                let mut matched_ratings = ratings.clone();
+                matched_ratings[index] = matched;
+                next.push((i, rule.output(), matched_ratings));
+            }
+            ratings[index] = unmatched;
+            if ratings[index].is_empty() {
+                break;
+            }
+        }
+        if ratings.iter().all(|r| !r.is_empty()) {
+            next.push((workflow.rules().len(), workflow.fallback(), ratings));
+        }
+
+        for (rule, output, ratings) in next {
+            rules.push(rule);
+            match output {
+                Output::Accepted => regions.push(Region {
+                    ratings,
+                    path: path.clone(),
+                    rules: rules.clone(),
+                }),
+                Output::Rejected => (),
+                Output::Rule(name) => self.split_regions(name, ratings, path, rules, regions),
+            }
+            rules.pop();
+        }
+
+        path.pop();
+    }
+}
+
+impl<'a> AcceptedRegions<'a> {
+    /// The amount of accepted parts.
+    pub fn volume(&self) -> u128 {
+        self.regions.iter().map(Region::volume).sum()
+    }
+
+    /// Returns the region a part is in, if it gets accepted.
+    pub fn find(&self, part: [u16; 4]) -> Option<&Region<'a>> {
+        self.regions.iter().find(|r| r.contains(part))
+    }
+
+    pub fn accepts(&self, part: [u16; 4]) -> bool {
+        self.find(part).is_some()
+    }
+
+    /// The amount of accepted parts that go through the given workflow.
+    pub fn volume_through(&self, workflow: &str) -> u128 {
+        self.regions
+            .iter()
+            .filter(|r| r.path.contains(&workflow))
+            .map(Region::volume)
+            .sum()
+    }
+
+    /// Covers the accepted parts with as few disjoint boxes as it can find.
+    ///
+    /// Starting from the regions, two touching boxes are merged with every box in the smallest box
+    /// around them, growing it until it doesn't cut through any box. If that box is exactly filled
+    /// they're all replaced by it, which also merges shapes like a pinwheel where no two boxes can
+    /// be merged on their own. This repeats until nothing can be merged, but finding the smallest
+    /// cover is NP-hard in general, so this can still miss it for some shapes.
+    pub fn minimal_boxes(&self) -> Vec<Ratings> {
+        let mut boxes = self
+            .regions
+            .iter()
+            .map(|r| Some(r.ratings.clone()))
+            .collect::<Vec<_>>();
+
+        let mut merged = true;
+        while merged {
+            merged = false;
+            for i in 0..boxes.len() {
+                for j in i + 1..boxes.len() {
+                    let (Some(a), Some(b)) = (&boxes[i], &boxes[j]) else {
+                        continue;
+                    };
+                    if !touching(a, b) {
+                        continue;
+                    }
+                    if let Some((hull, inside)) = filled_hull(&boxes, bounding_box(a, b)) {
+                        for k in inside {
+                            boxes[k] = None;
+                        }
+                        boxes[i] = Some(hull);
+                        merged = true;
+                    }
+                }
+            }
+        }
+
+        boxes.into_iter().flatten().collect()
+    }
+}
+
+/// Whether two boxes are next to each other along one rating and overlap along the others.
+fn touching(a: &Ratings, b: &Ratings) -> bool {
+    let next_to = |a: &RangeInclusive<u16>, b: &RangeInclusive<u16>| {
+        a.end().checked_add(1) == Some(*b.start()) || b.end().checked_add(1) == Some(*a.start())
+    };
+    let overlapping = |a: &RangeInclusive<u16>, b: &RangeInclusive<u16>| {
+        a.start() <= b.end() && b.start() <= a.end()
+    };
+
+    (0..4).any(|d| {
+        next_to(&a[d], &b[d]) && (0..4).all(|other| other == d || overlapping(&a[other], &b[other]))
+    })
+}
+
+/// The smallest box around two boxes.
+fn bounding_box(a: &Ratings, b: &Ratings) -> Ratings {
+    [0, 1, 2, 3].map(|d| *a[d].start().min(b[d].start())..=*a[d].end().max(b[d].end()))
+}
+
+/// Grows `hull` until it doesn't cut through any of the disjoint `boxes`. Returns the grown hull
+/// and the boxes inside it if they fill it exactly.
+fn filled_hull(boxes: &[Option<Ratings>], mut hull: Ratings) -> Option<(Ratings, Vec<usize>)> {
+    loop {
+        let mut grown = hull.clone();
+        let mut inside = vec![];
+        let mut filled = 0;
+        for (k, b) in boxes.iter().enumerate() {
+            let Some(b) = b else {
+                continue;
+            };
+            if (0..4).all(|d| b[d].start() <= hull[d].end() && hull[d].start() <= b[d].end()) {
+                grown = bounding_box(&grown, b);
+                inside.push(k);
+                filled += volume(b);
+            }
+        }
+
+        if grown == hull {
+            return (filled == volume(&hull)).then_some((hull, inside));
+        }
+        hull = grown;
+    }
+}
+
+pub fn volume(ratings: &Ratings) -> u128 {
+    ratings.iter().map(|r| r.len() as u128).product()
+}
+
+#[test]
+fn test_accepted_regions() {
+    let (workflows, ratings) = Day::INPUT_SAMPLE.split_once("\n\n").unwrap();
+    let program = Program::parse(workflows).unwrap();
+    let accepted = program.accepted_regions([1..=4000, 1..=4000, 1..=4000, 1..=4000]);
+    assert_eq!(167409079868000, accepted.volume());
+
+    for part in super::parse_ratings(ratings) {
+        assert_eq!(program.accepts(part), accepted.accepts(part));
+    }
+    let region = accepted.find([787, 2655, 1222, 2876]).unwrap();
+    assert_eq!(vec!["in", "qqz", "qs", "lnx"], region.path);
+    assert_eq!(vec![1, 0, 1, 0], region.rules);
+
+    // `in` is on every path, and everything through `lnx` is accepted
+    assert_eq!(accepted.volume(), accepted.volume_through("in"));
+    let lnx = 4000u128.pow(3) * (3448 - 2770);
+    assert_eq!(lnx, accepted.volume_through("lnx"));
+    assert_eq!(0, accepted.volume_through("nope"));
+
+    let boxes = accepted.minimal_boxes();
+    assert!(boxes.len() < accepted.regions.len());
+    assert_eq!(accepted.volume(), boxes.iter().map(volume).sum());
+}
+
+#[test]
+fn test_minimal_boxes() {
+    // a 3x3 square split into a pinwheel, where no two boxes can be merged on their own
+    let pinwheel = [
+        [0..=1, 0..=0],
+        [2..=2, 0..=1],
+        [1..=2, 2..=2],
+        [0..=0, 1..=2],
+        [1..=1, 1..=1],
+    ];
+    let regions = pinwheel
+        .into_iter()
+        .map(|[x, m]| Region {
+            ratings: [x, m, 1..=1, 1..=1],
+            path: vec![],
+            rules: vec![],
+        })
+        .collect::<Vec<_>>();
+    let accepted = AcceptedRegions {
+        bounds: [0..=2, 0..=2, 1..=1, 1..=1],
+        regions,
+    };
+    for a in &accepted.regions {
+        for b in &accepted.regions {
+            let pair = volume(&a.ratings) + volume(&b.ratings);
+            assert!(a == b || volume(&bounding_box(&a.ratings, &b.ratings)) > pair);
+        }
+    }
+    assert_eq!(vec![accepted.bounds.clone()], accepted.minimal_boxes());
+
+    // a square with a corner missing needs 2 boxes, however it's split
+    let mut accepted = accepted;
+    accepted.regions.remove(3);
+    accepted.regions[3].ratings[0] = 0..=1;
+    let boxes = accepted.minimal_boxes();
+    assert_eq!(2, boxes.len());
+    assert_eq!(8, accepted.volume());
+    assert_eq!(8, boxes.iter().map(volume).sum::<u128>());
+}
+
+#[test]
+fn test_accepted_regions_brute_force() {
+    let program = Program::parse(
+        "\
+in{x<4:lo,m>5:hi,a<3:A,R}
+lo{s>2:A,m<2:hi,R}
+hi{x>6:R,a>1:A,s<4:A,R}",
+    )
+    .unwrap();
+    let accepted = program.accepted_regions([1..=8, 1..=8, 0..=4, 2..=9]);
+
+    let mut through_hi = 0;
+    let mut total = 0;
+    for x in 1..=8 {
+        for m in 1..=8 {
+            for a in 0..=4 {
+                for s in 2..=9 {
+                    let part = [x, m, a, s];
+                    assert_eq!(program.accepts(part), accepted.accepts(part), "{part:?}");
+                    if let Some(region) = accepted.find(part) {
+                        total += 1;
+                        through_hi += region.path.contains(&"hi") as u128;
+                        assert_eq!(
+                            1,
+                            accepted.regions.iter().filter(|r| r.contains(part)).count()
+                        );
+                    }
+                }
+            }
+        }
+    }
+    assert_eq!(total, accepted.volume());
+    assert_eq!(through_hi, accepted.volume_through("hi"));
+
+    // a rule that takes every part within the bounds
+    let program = Program::parse("in{x<10:R,A}").unwrap();
+    assert_eq!(
+        0,
+        program
+            .accepted_regions([1..=5, 1..=5, 1..=5, 1..=5])
+            .volume()
+    );
+    assert_eq!(
+        5,
+        program
+            .accepted_regions([1..=14, 1..=1, 1..=1, 1..=1])
+            .volume()
+    );
+
+    // the boxes stay disjoint
+    let boxes = accepted.minimal_boxes();
+    assert_eq!(total, boxes.iter().map(volume).sum());
+    for (i, a) in boxes.iter().enumerate() {
+        for b in &boxes[i + 1..] {
+            assert!(a
+                .iter()
+                .zip(b)
+                .any(|(a, b)| a.end() < b.start() || b.end() < a.start()));
+        }
+    }
+}
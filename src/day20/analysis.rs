@@ -0,0 +1,510 @@
+//! Structural analysis of the modules feeding an output, to find when it gets a low pulse without
+//! simulating every button press.
+
+use std::{collections::VecDeque, fmt::Display, ops::ControlFlow};
+
+use super::{lcm, ModuleKind, Pulse, PulseEvent, PulseNetwork};
+
+#[cfg(test)]
+use {super::Day, crate::SolutionGold, crate::SolutionSilver};
+
+/// The modules feeding an output, split up by the inputs of the conjunction that sends pulses to
+/// the output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis<'a> {
+    /// The conjunction that sends pulses to the output.
+    pub conjunction: &'a str,
+    pub sub_circuits: Vec<SubCircuit<'a>>,
+}
+
+/// The modules that send pulses to one input of the final conjunction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubCircuit<'a> {
+    /// The input of the final conjunction.
+    pub output: &'a str,
+    /// Every module that can send pulses to `output`, except the broadcaster.
+    pub modules: Vec<&'a str>,
+    /// The binary counter driving `output`, or why it doesn't look like one.
+    pub counter: Result<Counter<'a>, Diagnostic<'a>>,
+}
+
+/// A chain of flip-flops counting button presses, with a conjunction that fires and resets the
+/// chain once all flip-flops it's fed by are on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counter<'a> {
+    pub conjunction: &'a str,
+    /// The flip-flops of the counter, from the lowest bit to the highest.
+    pub chain: Vec<&'a str>,
+    /// Whether each flip-flop in `chain` sends pulses to the conjunction.
+    pub bits: Vec<bool>,
+}
+
+/// The amount of presses until an output gets a low pulse, and how it was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LowPulse {
+    /// Read from the periods of the counters feeding the output.
+    Counters(usize),
+    /// Found by pressing the button, because the network doesn't fit the pattern for the given
+    /// reasons. `presses` is `None` if the output didn't get a low pulse in time.
+    Simulated {
+        presses: Option<usize>,
+        reasons: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalysisError {
+    MissingOutput,
+    MissingBroadcaster,
+    /// The output isn't fed by exactly one conjunction.
+    NotFedByConjunction,
+}
+
+/// Why a sub-circuit isn't a binary counter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic<'a> {
+    /// The input of the final conjunction isn't a conjunction with a single input.
+    NotAnInverter(&'a str),
+    /// The inverter isn't fed by a conjunction.
+    NotAConjunction(&'a str),
+    /// The counter conjunction is fed by this amount of flip-flop chains from the broadcaster,
+    /// instead of one.
+    Chains {
+        conjunction: &'a str,
+        count: usize,
+    },
+    /// A flip-flop that sends pulses to more than one flip-flop, or back into its own chain.
+    Branching(&'a str),
+    UnexpectedInput {
+        module: &'a str,
+        input: &'a str,
+    },
+    UnexpectedTarget {
+        module: &'a str,
+        target: &'a str,
+    },
+    /// The conjunction doesn't send pulses to the lowest flip-flop and every flip-flop it isn't
+    /// fed by, so it doesn't reset the counter.
+    WrongReset {
+        conjunction: &'a str,
+        flip_flop: &'a str,
+    },
+    /// The counter conjunction isn't fed by any flip-flop, so it never counts.
+    NoBits(&'a str),
+}
+
+impl Counter<'_> {
+    /// The amount of presses between the conjunction firing, which is the binary number formed
+    /// by the bits.
+    pub fn period(&self) -> usize {
+        let bits = self.bits.iter().rev();
+        bits.fold(0, |acc, bit| acc << 1 | *bit as usize)
+    }
+}
+
+impl<'a> PulseNetwork<'a> {
+    /// Splits the modules feeding `output` into sub-circuits, and recognises the ones that are
+    /// binary counters.
+    pub fn analyze(&self, output: &str) -> Result<Analysis<'a>, AnalysisError> {
+        let output = self.index_of(output).ok_or(AnalysisError::MissingOutput)?;
+        let broadcaster = self
+            .index_of("broadcaster")
+            .ok_or(AnalysisError::MissingBroadcaster)?;
+        let conjunction = match self.modules[output].inputs[..] {
+            [input] if self.modules[input].kind == ModuleKind::Conjunction => input,
+            _ => return Err(AnalysisError::NotFedByConjunction),
+        };
+
+        let sub_circuits = self.modules[conjunction]
+            .inputs
+            .iter()
+            .map(|&input| SubCircuit {
+                output: self.modules[input].name,
+                modules: self
+                    .feeding(input, broadcaster)
+                    .into_iter()
+                    .map(|i| self.modules[i].name)
+                    .collect(),
+                counter: self.counter(input, broadcaster),
+            })
+            .collect();
+
+        Ok(Analysis {
+            conjunction: self.modules[conjunction].name,
+            sub_circuits,
+        })
+    }
+
+    /// Returns every module that can send pulses to `index`, including itself but not the
+    /// broadcaster.
+    fn feeding(&self, index: usize, broadcaster: usize) -> Vec<usize> {
+        let mut seen = vec![false; self.modules.len()];
+        seen[index] = true;
+        seen[broadcaster] = true;
+
+        let mut modules = vec![];
+        let mut queue = VecDeque::from([index]);
+        while let Some(index) = queue.pop_front() {
+            modules.push(index);
+            for &input in &self.modules[index].inputs {
+                if !seen[input] {
+                    seen[input] = true;
+                    queue.push_back(input);
+                }
+            }
+        }
+        modules
+    }
+
+    /// Reads the counter driving `inverter` from the wiring.
+    fn counter(&self, inverter: usize, broadcaster: usize) -> Result<Counter<'a>, Diagnostic<'a>> {
+        let name = |i: usize| self.modules[i].name;
+        let is_flip_flop = |i: usize| self.modules[i].kind == ModuleKind::FlipFlop;
+
+        let module = &self.modules[inverter];
+        let counter = match module.inputs[..] {
+            [input] if module.kind == ModuleKind::Conjunction => input,
+            _ => return Err(Diagnostic::NotAnInverter(module.name)),
+        };
+        if self.modules[counter].kind != ModuleKind::Conjunction {
+            return Err(Diagnostic::NotAConjunction(name(counter)));
+        }
+        let conjunction = &self.modules[counter];
+
+        // the chain starts at the one flip-flop the broadcaster and the conjunction share
+        let starts = self.modules[broadcaster]
+            .targets
+            .iter()
+            .copied()
+            .filter(|&t| is_flip_flop(t))
+            .filter(|t| conjunction.targets.contains(t) || conjunction.inputs.contains(t))
+            .collect::<Vec<_>>();
+        let [start] = starts[..] else {
+            return Err(Diagnostic::Chains {
+                conjunction: conjunction.name,
+                count: starts.len(),
+            });
+        };
+
+        let mut chain = vec![start];
+        loop {
+            let current = *chain.last().unwrap();
+            let mut next = None;
+            for &target in &self.modules[current].targets {
+                if target == counter {
+                    continue;
+                }
+                if !is_flip_flop(target) {
+                    return Err(Diagnostic::UnexpectedTarget {
+                        module: name(current),
+                        target: name(target),
+                    });
+                }
+                if next.is_some() || chain.contains(&target) {
+                    return Err(Diagnostic::Branching(name(current)));
+                }
+                next = Some(target);
+            }
+
+            let Some(next) = next else { break };
+            chain.push(next);
+        }
+
+        // every flip-flop is only fed by the one before it, and by the conjunction resetting it
+        for (i, &flip_flop) in chain.iter().enumerate() {
+            let previous = if i == 0 { broadcaster } else { chain[i - 1] };
+            let unexpected = self.modules[flip_flop]
+                .inputs
+                .iter()
+                .find(|&&input| input != previous && input != counter);
+            if let Some(&input) = unexpected {
+                return Err(Diagnostic::UnexpectedInput {
+                    module: name(flip_flop),
+                    input: name(input),
+                });
+            }
+        }
+        if let Some(&input) = conjunction.inputs.iter().find(|i| !chain.contains(i)) {
+            return Err(Diagnostic::UnexpectedInput {
+                module: conjunction.name,
+                input: name(input),
+            });
+        }
+        let unexpected = conjunction
+            .targets
+            .iter()
+            .find(|&&target| target != inverter && !chain.contains(&target));
+        if let Some(&target) = unexpected {
+            return Err(Diagnostic::UnexpectedTarget {
+                module: conjunction.name,
+                target: name(target),
+            });
+        }
+
+        let bits = chain
+            .iter()
+            .map(|flip_flop| conjunction.inputs.contains(flip_flop))
+            .collect::<Vec<_>>();
+        if !bits.contains(&true) {
+            return Err(Diagnostic::NoBits(conjunction.name));
+        }
+
+        // once the counter fires, the conjunction adds the missing bits and one more, so the
+        // counter overflows back to 0
+        for (i, (&flip_flop, &bit)) in chain.iter().zip(&bits).enumerate() {
+            let resets = conjunction.targets.contains(&flip_flop);
+            if resets != (i == 0 || !bit) {
+                return Err(Diagnostic::WrongReset {
+                    conjunction: conjunction.name,
+                    flip_flop: name(flip_flop),
+                });
+            }
+        }
+
+        Ok(Counter {
+            conjunction: conjunction.name,
+            chain: chain.into_iter().map(name).collect(),
+            bits,
+        })
+    }
+
+    /// Finds the amount of presses until `output` gets a low pulse. The periods of the counters
+    /// are used when the network is made of them, otherwise the button is pressed on a copy of
+    /// the network up to `max_presses` times.
+    pub fn presses_until_low(&self, output: &str, max_presses: usize) -> LowPulse {
+        let reasons = match self.analyze(output) {
+            Ok(analysis) => match analysis.period() {
+                Some(period) => return LowPulse::Counters(period),
+                None => analysis.diagnostics().map(|d| d.to_string()).collect(),
+            },
+            Err(error) => vec![error.to_string()],
+        };
+
+        let mut network = self.clone();
+        network.presses = 0;
+        let presses = (0..max_presses).find_map(|_| {
+            let flow = network.press_button_with(&mut |event: &PulseEvent| {
+                if event.to == output && event.pulse == Pulse::Low {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            });
+            flow.is_break().then_some(network.presses)
+        });
+
+        LowPulse::Simulated { presses, reasons }
+    }
+
+    /// Presses the button on a copy of the network until `from` sends a high pulse to `to`, and
+    /// returns the amount of presses that took.
+    pub fn presses_until_high(&self, from: &str, to: &str, max_presses: usize) -> Option<usize> {
+        let mut network = self.clone();
+        network.presses = 0;
+        for _ in 0..max_presses {
+            let flow = network.press_button_with(&mut |event: &PulseEvent| {
+                if event.from == from && event.to == to && event.pulse == Pulse::High {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            });
+            if flow.is_break() {
+                return Some(network.presses);
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a> Analysis<'a> {
+    /// The amount of presses until the output gets a low pulse, which is when all counters fire
+    /// on the same press. `None` if any sub-circuit isn't a counter.
+    pub fn period(&self) -> Option<usize> {
+        self.sub_circuits
+            .iter()
+            .map(|s| s.counter.as_ref().ok().map(Counter::period))
+            .try_fold(1, |acc, period| Some(lcm(acc, period?)))
+    }
+
+    /// The reasons some sub-circuits aren't binary counters.
+    pub fn diagnostics(&self) -> impl Iterator<Item = &Diagnostic<'a>> {
+        self.sub_circuits
+            .iter()
+            .filter_map(|s| s.counter.as_ref().err())
+    }
+}
+
+impl Display for AnalysisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            AnalysisError::MissingOutput => "the output doesn't exist",
+            AnalysisError::MissingBroadcaster => "there is no broadcaster",
+            AnalysisError::NotFedByConjunction => "the output isn't fed by a single conjunction",
+        })
+    }
+}
+
+impl Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Diagnostic::NotAnInverter(name) => {
+                write!(f, "{name} isn't a conjunction with a single input")
+            }
+            Diagnostic::NotAConjunction(name) => write!(f, "{name} isn't a conjunction"),
+            Diagnostic::Chains { conjunction, count } => {
+                write!(
+                    f,
+                    "{conjunction} is fed by {count} flip-flop chains instead of 1"
+                )
+            }
+            Diagnostic::Branching(name) => {
+                write!(
+                    f,
+                    "{name} sends pulses to more than one flip-flop in its chain"
+                )
+            }
+            Diagnostic::UnexpectedInput { module, input } => {
+                write!(
+                    f,
+                    "{module} gets pulses from {input}, which isn't part of the counter"
+                )
+            }
+            Diagnostic::UnexpectedTarget { module, target } => {
+                write!(
+                    f,
+                    "{module} sends pulses to {target}, which isn't part of the counter"
+                )
+            }
+            Diagnostic::WrongReset {
+                conjunction,
+                flip_flop,
+            } => write!(
+                f,
+                "{conjunction} doesn't reset {flip_flop} like a counter would"
+            ),
+            Diagnostic::NoBits(name) => write!(f, "{name} isn't fed by any flip-flop"),
+        }
+    }
+}
+
+#[test]
+fn test_analyze() {
+    let network = PulseNetwork::parse(Day::INPUT_REAL);
+    let analysis = network.analyze("rx").unwrap();
+    assert_eq!("vr", analysis.conjunction);
+    assert_eq!(4, analysis.sub_circuits.len());
+    assert_eq!(0, analysis.diagnostics().count());
+
+    let pq = analysis
+        .sub_circuits
+        .iter()
+        .find(|s| s.output == "pq")
+        .unwrap();
+    let counter = pq.counter.as_ref().unwrap();
+    assert_eq!("fs", counter.conjunction);
+    assert_eq!(
+        vec!["nk", "pj", "mh", "jb", "rg", "jz", "pm", "hd", "vn", "rt", "kz", "dt"],
+        counter.chain
+    );
+    assert_eq!(0b111110100001, counter.period());
+    assert_eq!(14, pq.modules.len());
+
+    assert_eq!(
+        LowPulse::Counters(238920142622879),
+        network.presses_until_low("rx", 0)
+    );
+
+    // the periods read from the wiring match the simulation
+    assert_eq!(
+        Some(counter.period()),
+        network.presses_until_high("pq", "vr", 10_000)
+    );
+
+    let sample = PulseNetwork::parse(Day::INPUT_SAMPLE);
+    assert_eq!(Err(AnalysisError::MissingOutput), sample.analyze("rx"));
+    assert_eq!(Err(AnalysisError::NotFedByConjunction), sample.analyze("a"));
+}
+
+#[test]
+fn test_analyze_fallback() {
+    // `a` and `b` count to 3, but `i2` is fed by a flip-flop straight from the broadcaster
+    let input = "\
+broadcaster -> a, x
+%a -> b, c1
+%b -> c1
+&c1 -> a, i1
+&i1 -> out
+%x -> i2
+&i2 -> out
+&out -> rx";
+    let network = PulseNetwork::parse(input);
+    let analysis = network.analyze("rx").unwrap();
+    assert_eq!(
+        vec![&Diagnostic::NotAConjunction("x")],
+        analysis.diagnostics().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        "x isn't a conjunction",
+        analysis.diagnostics().next().unwrap().to_string()
+    );
+    assert_eq!(None, analysis.period());
+    assert_eq!(
+        LowPulse::Simulated {
+            presses: Some(6),
+            reasons: vec!["x isn't a conjunction".to_string()],
+        },
+        network.presses_until_low("rx", 100)
+    );
+    assert_eq!(6, Day::calculate_gold(input));
+
+    // `i2` first fires on press 4, but `rx` gets a low pulse on press 6 rather than on press 12
+    let input = input.replace("%x -> i2", "%x -> y\n%y -> i2");
+    let network = PulseNetwork::parse(&input);
+    assert_eq!(Some(4), network.presses_until_high("i2", "out", 100));
+    assert_eq!(
+        LowPulse::Simulated {
+            presses: Some(6),
+            reasons: vec!["y isn't a conjunction".to_string()],
+        },
+        network.presses_until_low("rx", 100)
+    );
+    assert_eq!(6, Day::calculate_gold(&input));
+
+    // `rx` only ever gets high pulses
+    let network = PulseNetwork::parse("broadcaster -> b\n&b -> c\n&c -> out\n&out -> rx");
+    assert_eq!(
+        LowPulse::Simulated {
+            presses: None,
+            reasons: vec!["b is fed by 0 flip-flop chains instead of 1".to_string()],
+        },
+        network.presses_until_low("rx", 100)
+    );
+    let network = PulseNetwork::parse("broadcaster -> rx");
+    assert_eq!(
+        LowPulse::Simulated {
+            presses: Some(1),
+            reasons: vec!["the output isn't fed by a single conjunction".to_string()],
+        },
+        network.presses_until_low("rx", 100)
+    );
+
+    // a counter that doesn't reset itself
+    let input = input.replace("&c1 -> a, i1", "&c1 -> b, i1");
+    let network = PulseNetwork::parse(&input);
+    let analysis = network.analyze("rx").unwrap();
+    assert_eq!(
+        Err(Diagnostic::WrongReset {
+            conjunction: "c1",
+            flip_flop: "a",
+        }),
+        analysis.sub_circuits[0].counter
+    );
+}
+
+#[test]
+#[should_panic(expected = "counters: b is fed by 0 flip-flop chains instead of 1")]
+fn test_gold_no_low_pulse() {
+    Day::calculate_gold("broadcaster -> b\n&b -> c\n&c -> out\n&out -> rx");
+}
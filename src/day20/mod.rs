@@ -1,9 +1,13 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, ops::ControlFlow};
 
 use ahash::AHashMap;
 
 use super::*;
 
+pub mod analysis;
+
+use analysis::LowPulse;
+
 pub struct Day;
 
 impl SolutionSilver<usize> for Day {
@@ -12,271 +16,377 @@ impl SolutionSilver<usize> for Day {
     const INPUT_REAL: &'static str = include_str!("input_real.txt");
 
     fn calculate_silver(input: &str) -> usize {
-        let map = input
-            .lines()
-            .map(|l| {
-                let (start, targets) = l.split_once(" -> ").unwrap();
+        let mut network = PulseNetwork::parse(input);
+        for _ in 0..1000 {
+            _ = network.press_button_with(&mut |_: &PulseEvent| ControlFlow::Continue(()));
+        }
 
-                let (prefix, module) = match start.chars().next().unwrap() {
-                    '&' => (Some('&'), start.trim_start_matches('&')),
-                    '%' => (Some('%'), start.trim_start_matches('%')),
-                    _ => (None, start),
-                };
+        let (low, high) = network.pulse_totals();
+        low * high
+    }
+}
 
-                (module, (prefix, targets))
-            })
-            .collect::<AHashMap<_, _>>();
+/// How many presses to simulate before giving up on finding a low pulse to `rx`.
+const MAX_PRESSES: usize = 1_000_000;
 
-        // convert parsed lines to nodes
-        let mut nodes = map
-            .iter()
-            .map(|(module, (prefix, targets))| {
-                let targets = targets.split(", ").collect::<Vec<_>>();
-                let node = match prefix {
-                    Some('%') => Node::FlipFlop { state: false },
-                    Some('&') => Node::Conjunction {
-                        input_states: map
-                            .iter()
-                            .filter(|(_, (_, v_targets))| v_targets.contains(module))
-                            .map(|(k, (_, _))| (*k, false))
-                            .collect::<Vec<_>>(),
-                    },
-                    None => Node::Broadcast,
-                    _ => unreachable!(),
-                };
+impl SolutionGold<usize, usize> for Day {
+    fn calculate_gold(input: &str) -> usize {
+        let network = PulseNetwork::parse(input);
+        assert!(
+            network.index_of("rx").is_some(),
+            "The network has no rx module"
+        );
+
+        // `rx` is usually fed by counters that each fire once every few thousand presses, and it
+        // gets a low pulse on the press they all fire on. Only other networks are simulated.
+        match network.presses_until_low("rx", MAX_PRESSES) {
+            LowPulse::Counters(presses) => presses,
+            LowPulse::Simulated {
+                presses: Some(presses),
+                ..
+            } => presses,
+            LowPulse::Simulated {
+                presses: None,
+                reasons,
+            } => panic!(
+                "rx got no low pulse within {MAX_PRESSES} presses, and the network isn't made of \
+                 counters: {}",
+                reasons.join(", ")
+            ),
+        }
+    }
+}
 
-                (*module, (node, targets))
-            })
-            .collect::<AHashMap<_, _>>();
+// https://www.hackertouch.com/least-common-multiple-in-rust.html
+fn lcm(first: usize, second: usize) -> usize {
+    first * second / gcd(first, second)
+}
 
-        let mut count_high = 0;
-        let mut count_low = 0;
+fn gcd(first: usize, second: usize) -> usize {
+    let mut max = first;
+    let mut min = second;
+    if min > max {
+        std::mem::swap(&mut max, &mut min);
+    }
 
-        let mut queue = VecDeque::new();
-        for _iteration in 0..1000 {
-            queue.push_back(("button", "broadcaster", false));
+    loop {
+        let res = max % min;
+        if res == 0 {
+            return min;
+        }
 
-            while let Some((from, to, incoming_signal)) = queue.pop_front() {
-                if incoming_signal {
-                    count_high += 1;
-                } else {
-                    count_low += 1;
-                }
+        max = min;
+        min = res;
+    }
+}
 
-                let Some((node, targets)) = nodes.get_mut(to) else {
-                    continue;
-                };
+/// The name used for the sender of the pulse the button sends to the broadcaster.
+pub const BUTTON: &str = "button";
 
-                let outgoing_signal = match node {
-                    Node::FlipFlop { state } => {
-                        if incoming_signal {
-                            continue;
-                        }
-
-                        // flip if low
-                        *state = !*state;
-
-                        *state
-                    }
-                    Node::Conjunction { input_states } => {
-                        if let Some((_, v)) = input_states.iter_mut().find(|(k, _)| *k == from) {
-                            *v = incoming_signal;
-                        }
-
-                        !input_states.iter().all(|(_, v)| *v)
-                    }
-                    Node::Broadcast => incoming_signal,
-                };
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pulse {
+    Low,
+    High,
+}
 
-                for target in targets {
-                    queue.push_back((to, *target, outgoing_signal));
-                }
-            }
-        }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PulseEvent<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub pulse: Pulse,
+}
 
-        count_low * count_high
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleKind {
+    Broadcast,
+    FlipFlop,
+    Conjunction,
+    /// A module that's only sent pulses to, like `output` or `rx`.
+    Untyped,
 }
 
-impl SolutionGold<usize, usize> for Day {
-    fn calculate_gold(input: &str) -> usize {
-        let map = input
-            .lines()
-            .map(|l| {
-                let (start, end) = l.split_once(" -> ").unwrap();
+/// The amount of pulses a module received and sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PulseCounters {
+    pub low_received: usize,
+    pub high_received: usize,
+    pub low_sent: usize,
+    pub high_sent: usize,
+}
 
-                let (prefix, module) = match start.chars().next().unwrap() {
-                    '&' => (Some('&'), start.trim_start_matches('&')),
-                    '%' => (Some('%'), start.trim_start_matches('%')),
-                    _ => (None, start),
-                };
-                let targets = end.split(", ").collect::<Vec<_>>();
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Module<'a> {
+    pub name: &'a str,
+    pub kind: ModuleKind,
+    /// Indices of the modules this one sends pulses to.
+    pub targets: Vec<usize>,
+    /// Indices of the modules that send pulses to this one.
+    pub inputs: Vec<usize>,
+    pub counters: PulseCounters,
+    /// For each target, which of its inputs this module is.
+    target_slots: Vec<usize>,
+    /// Whether a flip-flop is on.
+    on: bool,
+    /// The last pulse a conjunction got from each input, `true` for high.
+    memory: Vec<bool>,
+}
 
-                (module, (prefix, targets))
-            })
-            .collect::<AHashMap<_, _>>();
+/// Gets told about every pulse sent while pressing the button, and can stop the button press.
+pub trait PulseObserver<'a> {
+    fn on_pulse(&mut self, event: &PulseEvent<'a>) -> ControlFlow<()>;
+}
 
-        if !map.values().any(|v| v.1.contains(&"rx")) {
-            return usize::MAX;
-        }
+impl<'a, F: FnMut(&PulseEvent<'a>) -> ControlFlow<()>> PulseObserver<'a> for F {
+    fn on_pulse(&mut self, event: &PulseEvent<'a>) -> ControlFlow<()> {
+        self(event)
+    }
+}
 
-        // convert parsed lines to nodes
-        let mut nodes = map
-            .iter()
-            .map(|(module, (prefix, targets))| {
-                let node = match prefix {
-                    Some('%') => Node::FlipFlop { state: false },
-                    Some('&') => Node::Conjunction {
-                        input_states: map
-                            .iter()
-                            .filter(|(_, (_, v_targets))| v_targets.contains(module))
-                            .map(|(k, (_, _))| (*k, false))
-                            .collect::<Vec<_>>(),
-                    },
-                    None => Node::Broadcast,
-                    _ => unreachable!(),
-                };
+/// A network of modules that pulses travel through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PulseNetwork<'a> {
+    modules: Vec<Module<'a>>,
+    indices: AHashMap<&'a str, usize>,
+    presses: usize,
+}
 
-                (*module, (node, targets))
-            })
-            .collect::<AHashMap<_, _>>();
-
-        // run simulation for a while
-        const SAMPLE_SIZE: usize = 10_000;
-        let mut queue = VecDeque::new();
-        let mut states = vec![];
-        for iteration in 0..SAMPLE_SIZE {
-            queue.push_back(("button", "broadcaster", false));
-
-            while let Some((from, to, incoming_signal)) = queue.pop_front() {
-                // this is not going to get hit, but it's here for correctness
-                if to == "rx" && !incoming_signal {
-                    return iteration + 1;
-                }
+impl<'a> PulseNetwork<'a> {
+    pub fn parse(input: &'a str) -> Self {
+        let mut network = Self {
+            modules: vec![],
+            indices: AHashMap::new(),
+            presses: 0,
+        };
 
-                let Some((node, targets)) = nodes.get_mut(to) else {
-                    continue;
-                };
+        let lines = input
+            .lines()
+            .map(|l| {
+                let (start, targets) = l.split_once(" -> ").unwrap();
 
-                let outgoing_signal = match node {
-                    Node::FlipFlop { state } => {
-                        if incoming_signal {
-                            continue;
-                        }
-
-                        // flip if low
-                        *state = !*state;
-
-                        *state
-                    }
-                    Node::Conjunction { input_states } => {
-                        if let Some((_, v)) = input_states.iter_mut().find(|(k, _)| *k == from) {
-                            *v = incoming_signal;
-                        }
-
-                        !input_states.iter().all(|(_, v)| *v)
-                    }
-                    Node::Broadcast => incoming_signal,
+                let (kind, name) = match start.as_bytes()[0] {
+                    b'&' => (ModuleKind::Conjunction, &start[1..]),
+                    b'%' => (ModuleKind::FlipFlop, &start[1..]),
+                    _ => (ModuleKind::Broadcast, start),
                 };
 
-                for target in *targets {
-                    queue.push_back((to, *target, outgoing_signal));
-                }
+                let index = network.add_module(name);
+                network.modules[index].kind = kind;
+                (index, targets)
+            })
+            .collect::<Vec<_>>();
+
+        for (index, targets) in lines {
+            for target in targets.split(", ") {
+                let target = network.add_module(target);
+                let slot = network.modules[target].inputs.len();
+                network.modules[target].inputs.push(index);
+                network.modules[target].memory.push(false);
+                network.modules[index].targets.push(target);
+                network.modules[index].target_slots.push(slot);
             }
+        }
 
-            // NOTE: this order is unstable, uses compile-time rng!
-            let nodes_values = nodes
-                .values()
-                .map(|v| (v.0.fold_state_to_int()))
-                .collect::<Vec<_>>();
+        network
+    }
 
-            states.push(nodes_values);
+    /// Returns the index of a module, adding an untyped module if it doesn't exist yet.
+    fn add_module(&mut self, name: &'a str) -> usize {
+        if let Some(index) = self.index_of(name) {
+            return index;
         }
 
-        // we've run the loop for a while, but we didn't write `high` to `rx`
-        // assume we're in a very large loop and we'll write to `rx` at the end of the loop
-        // this is a stupid assumption, but it seems to be how the challenge was designed
-
-        let state_len = states.first().unwrap().len();
-        let mut loops = vec![];
-        'len: for i in 0..state_len {
-            // check if this is a loop
-            for loop_len in 1.. {
-                let mut state_chunks = states.chunks(loop_len);
-                let first_chunk_of_states = state_chunks.next().unwrap();
-                let is_loop = state_chunks.all(|chunk_of_states| {
-                    first_chunk_of_states
-                        .iter()
-                        .zip(chunk_of_states.iter())
-                        .all(|(a, b)| a[i] == b[i])
-                });
-
-                if is_loop {
-                    loops.push(loop_len);
-                    continue 'len;
-                }
-            }
+        self.modules.push(Module {
+            name,
+            kind: ModuleKind::Untyped,
+            targets: vec![],
+            inputs: vec![],
+            counters: PulseCounters::default(),
+            target_slots: vec![],
+            on: false,
+            memory: vec![],
+        });
+        self.indices.insert(name, self.modules.len() - 1);
+        self.modules.len() - 1
+    }
 
-            panic!("no loop found for idx {}", i);
-        }
+    pub fn modules(&self) -> &[Module<'a>] {
+        &self.modules
+    }
 
-        loops.iter().fold(1, |acc, len| lcm(acc, *len))
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.indices.get(name).copied()
     }
-}
 
-// https://www.hackertouch.com/least-common-multiple-in-rust.html
-fn lcm(first: usize, second: usize) -> usize {
-    first * second / gcd(first, second)
-}
+    pub fn module(&self, name: &str) -> Option<&Module<'a>> {
+        self.index_of(name).map(|i| &self.modules[i])
+    }
 
-fn gcd(first: usize, second: usize) -> usize {
-    let mut max = first;
-    let mut min = second;
-    if min > max {
-        std::mem::swap(&mut max, &mut min);
+    /// The amount of times the button has been pressed.
+    pub fn presses(&self) -> usize {
+        self.presses
     }
 
-    loop {
-        let res = max % min;
-        if res == 0 {
-            return min;
+    /// Returns the total amount of low and high pulses sent, including the ones from the button.
+    pub fn pulse_totals(&self) -> (usize, usize) {
+        self.modules.iter().fold((0, 0), |(low, high), m| {
+            (
+                low + m.counters.low_received,
+                high + m.counters.high_received,
+            )
+        })
+    }
+
+    /// Returns the state of each module as a number: whether a flip-flop is on, or a bitmask of
+    /// the inputs a conjunction last got a high pulse from.
+    pub fn state(&self) -> Vec<u64> {
+        self.modules
+            .iter()
+            .map(|m| match m.kind {
+                ModuleKind::FlipFlop => m.on as u64,
+                ModuleKind::Conjunction => {
+                    let bits = m.memory.iter().rev();
+                    bits.fold(0, |acc, high| acc << 1 | *high as u64)
+                }
+                ModuleKind::Broadcast | ModuleKind::Untyped => 0,
+            })
+            .collect()
+    }
+
+    /// Presses the button and returns every pulse that was sent, in order.
+    pub fn press_button(&mut self) -> Vec<PulseEvent<'a>> {
+        let mut events = vec![];
+        _ = self.press_button_with(&mut |event: &PulseEvent<'a>| {
+            events.push(*event);
+            ControlFlow::Continue(())
+        });
+        events
+    }
+
+    /// Presses the button and tells the observer about every pulse, before it's received. If the
+    /// observer breaks, that pulse isn't delivered and the remaining pulses are dropped.
+    pub fn press_button_with(&mut self, observer: &mut impl PulseObserver<'a>) -> ControlFlow<()> {
+        self.presses += 1;
+
+        let Some(broadcaster) = self.index_of("broadcaster") else {
+            return ControlFlow::Continue(());
+        };
+
+        // (from, to, slot of `from` in the inputs of `to`, pulse)
+        let mut queue = VecDeque::from([(None, broadcaster, 0, Pulse::Low)]);
+        while let Some((from, to, slot, pulse)) = queue.pop_front() {
+            let event = PulseEvent {
+                from: from.map_or(BUTTON, |f: usize| self.modules[f].name),
+                to: self.modules[to].name,
+                pulse,
+            };
+            observer.on_pulse(&event)?;
+
+            let module = &mut self.modules[to];
+            match pulse {
+                Pulse::Low => module.counters.low_received += 1,
+                Pulse::High => module.counters.high_received += 1,
+            }
+
+            let outgoing = match module.kind {
+                ModuleKind::FlipFlop if pulse == Pulse::High => continue,
+                ModuleKind::FlipFlop => {
+                    // flip if low
+                    module.on = !module.on;
+                    module.on
+                }
+                ModuleKind::Conjunction => {
+                    module.memory[slot] = pulse == Pulse::High;
+                    !module.memory.iter().all(|high| *high)
+                }
+                ModuleKind::Broadcast => pulse == Pulse::High,
+                ModuleKind::Untyped => continue,
+            };
+            let outgoing = if outgoing { Pulse::High } else { Pulse::Low };
+
+            match outgoing {
+                Pulse::Low => module.counters.low_sent += module.targets.len(),
+                Pulse::High => module.counters.high_sent += module.targets.len(),
+            }
+            for (target, slot) in module.targets.iter().zip(&module.target_slots) {
+                queue.push_back((Some(to), *target, *slot, outgoing));
+            }
         }
 
-        max = min;
-        min = res;
+        ControlFlow::Continue(())
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum Node<'i> {
-    FlipFlop {
-        state: bool,
-    },
-    Conjunction {
-        input_states: Vec<(&'i str, bool)>, // essentially a map
-    },
-    Broadcast,
+#[test]
+fn test_press_button() {
+    let mut network = PulseNetwork::parse(Day::INPUT_SAMPLE);
+    let events = network.press_button();
+    let rendered = events
+        .iter()
+        .map(|e| format!("{} -{:?}-> {}", e.from, e.pulse, e.to))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            "button -Low-> broadcaster",
+            "broadcaster -Low-> a",
+            "broadcaster -Low-> b",
+            "broadcaster -Low-> c",
+            "a -High-> b",
+            "b -High-> c",
+            "c -High-> inv",
+            "inv -Low-> a",
+            "a -Low-> b",
+            "b -Low-> c",
+            "c -Low-> inv",
+            "inv -High-> a",
+        ],
+        rendered
+    );
+
+    let a = network.module("a").unwrap();
+    assert_eq!(ModuleKind::FlipFlop, a.kind);
+    assert_eq!(
+        PulseCounters {
+            low_received: 2,
+            high_received: 1,
+            low_sent: 1,
+            high_sent: 1,
+        },
+        a.counters
+    );
+    assert_eq!((8, 4), network.pulse_totals());
 }
 
-impl Node<'_> {
-    fn fold_state_to_int(&self) -> u32 {
-        match self {
-            Node::FlipFlop { state } => {
-                if *state {
-                    1
-                } else {
-                    0
-                }
+#[test]
+fn test_press_button_with_observer() {
+    let mut network = PulseNetwork::parse(include_str!("input_sample_2.txt"));
+    assert_eq!(ModuleKind::Untyped, network.module("output").unwrap().kind);
+
+    // watch the pulses going into `con`
+    let mut seen = vec![];
+    for _ in 0..4 {
+        let flow = network.press_button_with(&mut |event: &PulseEvent| {
+            if event.to == "con" {
+                seen.push((event.from.to_string(), event.pulse));
             }
-            Node::Conjunction { input_states } => input_states
-                .iter()
-                .enumerate()
-                .fold(0, |acc, (i, (_, state))| {
-                    acc | if *state { 1 << i } else { 0 }
-                }),
-            Node::Broadcast => 0,
-        }
+            ControlFlow::Continue(())
+        });
+        assert!(flow.is_continue());
     }
+    assert_eq!(6, seen.len());
+    assert_eq!(("a".to_string(), Pulse::High), seen[0]);
+
+    // stop as soon as `output` gets a high pulse
+    let mut network = PulseNetwork::parse(include_str!("input_sample_2.txt"));
+    let flow = network.press_button_with(&mut |event: &PulseEvent| {
+        if event.to == "output" && event.pulse == Pulse::High {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    assert!(flow.is_break());
+    assert_eq!(0, network.module("output").unwrap().counters.high_received);
+    assert_eq!(1, network.presses());
 }
 
 #[test]
@@ -298,9 +408,9 @@ fn test_silver_real() {
 }
 
 #[test]
+#[should_panic(expected = "The network has no rx module")]
 fn test_gold_sample() {
-    let output = Day::calculate_gold(Day::INPUT_SAMPLE_GOLD);
-    assert_eq!(usize::MAX, output);
+    Day::calculate_gold(Day::INPUT_SAMPLE_GOLD);
 }
 
 #[test]
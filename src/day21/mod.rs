@@ -120,7 +120,7 @@ impl SolutionGold<usize, usize> for Day {
 
         let first_grid_endpoints = calculate_grid_endpoints(start_position, 0, MAX_DISTANCE);
 
-        let (odd_grids_endpoints, even_grids_endpoints) = if start_position % 2 != 0 {
+        let (odd_grids_endpoints, even_grids_endpoints) = if !start_position.is_multiple_of(2) {
             debug_assert_eq!(
                 first_grid_endpoints,
                 calculate_grid_endpoints(0, 0, usize::MAX)
@@ -175,7 +175,7 @@ impl SolutionGold<usize, usize> for Day {
         // the grid has an odd size so the amount of reachable tiles is different for even and odd
         // grids. The formula is `n*(n-1)*4`, where `n` is the amount of complete grids in 1
         // direction.
-        let even_grid_count = (complete_grid_count_including_start + 1) / 2;
+        let even_grid_count = complete_grid_count_including_start.div_ceil(2);
         let even_grid_count = even_grid_count * (even_grid_count - 1) * 4;
         let odd_grid_count = complete_grid_count_including_start / 2;
         let odd_grid_count = odd_grid_count * odd_grid_count * 4;
@@ -204,10 +204,10 @@ impl SolutionGold<usize, usize> for Day {
         let distance_to_outer_edge_grid = (width + 1) + width * (outer_edge_count - 1);
         let distance_to_inner_edge_grid = distance_to_outer_edge_grid - width;
 
-        debug_assert!((distance_to_outer_corner_grid - (width / 2 + 1)) % width == 0);
-        debug_assert!((distance_to_inner_corner_grid - (width / 2 + 1)) % width == 0);
-        debug_assert!((distance_to_inner_edge_grid - (width + 1)) % width == 0);
-        debug_assert!((distance_to_outer_edge_grid - (width + 1)) % width == 0);
+        debug_assert!((distance_to_outer_corner_grid - (width / 2 + 1)).is_multiple_of(width));
+        debug_assert!((distance_to_inner_corner_grid - (width / 2 + 1)).is_multiple_of(width));
+        debug_assert!((distance_to_inner_edge_grid - (width + 1)).is_multiple_of(width));
+        debug_assert!((distance_to_outer_edge_grid - (width + 1)).is_multiple_of(width));
 
         // Now iterate over each corner/edge and calculate the amount of endpoints in them.
         let x_left = 0;
@@ -55,6 +55,16 @@ pub fn split_once_2(haystack: &[u8], delimiter: u8) -> (&[u8], &[u8]) {
     }
 }
 
+/// A tiny xorshift PRNG, for tests and sampling where pulling in a crate isn't worth it.
+///
+/// `state` must not be 0.
+pub fn xorshift64(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;